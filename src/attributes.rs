@@ -0,0 +1,52 @@
+/// Values of the upper byte of "version made by" whose external attributes hold a
+/// Unix mode in their upper 16 bits.
+const HOST_UNIX: u8 = 3;
+const HOST_OSX: u8 = 19;
+
+/// Unix file type bits.
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+
+/// MS-DOS attribute flags, in the lower byte of the external attributes.
+pub const DOS_READ_ONLY: u8 = 0x01;
+pub const DOS_HIDDEN: u8 = 0x02;
+pub const DOS_SYSTEM: u8 = 0x04;
+pub const DOS_DIRECTORY: u8 = 0x10;
+pub const DOS_ARCHIVE: u8 = 0x20;
+
+/// What an entry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A regular file.
+    File,
+    /// A directory, which has no data.
+    Directory,
+    /// A symbolic link, whose data is the path it points to.
+    Symlink,
+}
+
+/// Returns the Unix mode held by the external attributes, if the entry was written
+/// on a Unix-like system and has one.
+pub(crate) fn unix_mode(version_made_by: u16, external_file_attributes: u32) -> Option<u32> {
+    let host = (version_made_by >> 8) as u8;
+    let mode = external_file_attributes >> 16;
+    (matches!(host, HOST_UNIX | HOST_OSX) && mode != 0).then_some(mode)
+}
+
+/// Classifies an entry from its Unix mode, or else its MS-DOS attributes and name.
+pub(crate) fn entry_kind(
+    unix_mode: Option<u32>,
+    external_file_attributes: u32,
+    filename: &str,
+) -> EntryKind {
+    match unix_mode.map(|mode| mode & S_IFMT) {
+        Some(S_IFLNK) => EntryKind::Symlink,
+        Some(S_IFDIR) => EntryKind::Directory,
+        Some(_) => EntryKind::File,
+        None if external_file_attributes as u8 & DOS_DIRECTORY != 0 || filename.ends_with('/') => {
+            EntryKind::Directory
+        }
+        None => EntryKind::File,
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::{Duration, SystemTime};
+
+/// A date and time in the MS-DOS format used by zip headers.
+///
+/// It has a two second resolution and no time zone; the fields are usually in local
+/// time. The raw values are kept as-is, so they may not form a valid date, in which
+/// case the conversions below return `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DosDateTime {
+    pub date: u16,
+    pub time: u16,
+}
+
+impl DosDateTime {
+    pub fn new(date: u16, time: u16) -> Self {
+        Self { date, time }
+    }
+
+    /// Returns the year, from 1980 to 2107.
+    pub fn year(&self) -> u16 {
+        1980 + (self.date >> 9)
+    }
+
+    /// Returns the month, which should be from 1 to 12.
+    pub fn month(&self) -> u8 {
+        ((self.date >> 5) & 0x0f) as u8
+    }
+
+    /// Returns the day of the month, which should be from 1 to 31.
+    pub fn day(&self) -> u8 {
+        (self.date & 0x1f) as u8
+    }
+
+    /// Returns the hour, which should be from 0 to 23.
+    pub fn hour(&self) -> u8 {
+        (self.time >> 11) as u8
+    }
+
+    /// Returns the minute, which should be from 0 to 59.
+    pub fn minute(&self) -> u8 {
+        ((self.time >> 5) & 0x3f) as u8
+    }
+
+    /// Returns the second, always even, which should be from 0 to 58.
+    pub fn second(&self) -> u8 {
+        ((self.time & 0x1f) * 2) as u8
+    }
+
+    /// Returns true when the fields form an actual date and time.
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month())
+            && (1..=days_in_month(self.year(), self.month())).contains(&self.day())
+            && self.hour() < 24
+            && self.minute() < 60
+            && self.second() < 60
+    }
+
+    /// Converts the date and time to a `SystemTime`, taking it to be in UTC as the
+    /// actual time zone is unknown.
+    ///
+    /// Returns `None` when the fields do not form a valid date and time.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        if !self.is_valid() {
+            return None;
+        }
+        let days = days_since_epoch(self.year(), self.month(), self.day());
+        let seconds = days * 86400
+            + self.hour() as u64 * 3600
+            + self.minute() as u64 * 60
+            + self.second() as u64;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    /// Converts the date and time to a `chrono::NaiveDateTime`.
+    ///
+    /// Returns `None` when the fields do not form a valid date and time.
+    #[cfg(feature = "chrono")]
+    pub fn to_chrono(&self) -> Option<chrono::NaiveDateTime> {
+        chrono::NaiveDate::from_ymd_opt(self.year() as i32, self.month() as u32, self.day() as u32)?
+            .and_hms_opt(
+                self.hour() as u32,
+                self.minute() as u32,
+                self.second() as u32,
+            )
+    }
+
+    /// Converts the date and time to a `time::PrimitiveDateTime`.
+    ///
+    /// Returns `None` when the fields do not form a valid date and time.
+    #[cfg(feature = "time")]
+    pub fn to_time(&self) -> Option<time::PrimitiveDateTime> {
+        let date = time::Date::from_calendar_date(
+            self.year() as i32,
+            time::Month::try_from(self.month()).ok()?,
+            self.day(),
+        )
+        .ok()?;
+        let time = time::Time::from_hms(self.hour(), self.minute(), self.second()).ok()?;
+        Some(time::PrimitiveDateTime::new(date, time))
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to the given date, which must be valid
+/// and not before 1980.
+fn days_since_epoch(year: u16, month: u8, day: u8) -> u64 {
+    let past_years = (1970..year)
+        .map(|year| if is_leap_year(year) { 366 } else { 365 })
+        .sum::<u64>();
+    let past_months = (1..month)
+        .map(|month| days_in_month(year, month) as u64)
+        .sum::<u64>();
+    past_years + past_months + day as u64 - 1
+}
+
+/// Number of seconds from 1601-01-01, the NTFS epoch, to 1970-01-01.
+const NTFS_EPOCH_OFFSET: u64 = 11_644_473_600;
+
+/// Converts a number of seconds since 1970-01-01 UTC to a `SystemTime`.
+pub(crate) fn unix_time(seconds: i64) -> Option<SystemTime> {
+    let duration = Duration::from_secs(seconds.unsigned_abs());
+    if seconds < 0 {
+        SystemTime::UNIX_EPOCH.checked_sub(duration)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_add(duration)
+    }
+}
+
+/// Converts a number of 100 ns intervals since 1601-01-01 UTC to a `SystemTime`.
+pub(crate) fn ntfs_time(ticks: u64) -> Option<SystemTime> {
+    let since_ntfs_epoch = Duration::new(ticks / 10_000_000, (ticks % 10_000_000) as u32 * 100);
+    let offset = Duration::from_secs(NTFS_EPOCH_OFFSET);
+    match since_ntfs_epoch.checked_sub(offset) {
+        Some(duration) => SystemTime::UNIX_EPOCH.checked_add(duration),
+        None => SystemTime::UNIX_EPOCH.checked_sub(offset - since_ntfs_epoch),
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use miniz_oxide::{
+    inflate::stream::{inflate, InflateState},
+    DataFormat, MZError, MZFlush, MZStatus,
+};
+
+use crate::{CompressionMethod, Error, FormatError};
+
+/// Size of the buffer the deflate decoder writes its output to.
+const OUTPUT_BUFFER_SIZE: usize = 32 * 1024;
+
+/// Incrementally decompresses the data of a single entry, keeping track of the
+/// CRC-32 and size of the output.
+pub(crate) struct Decoder {
+    inflater: Option<Box<InflateState>>,
+    output: Vec<u8>,
+    hasher: crc32fast::Hasher,
+    uncompressed_size: u64,
+    finished: bool,
+}
+
+impl Decoder {
+    pub(crate) fn new(method: CompressionMethod) -> Result<Self, Error> {
+        let inflater = match method {
+            CompressionMethod::Stored => None,
+            CompressionMethod::Deflated => Some(InflateState::new_boxed(DataFormat::Raw)),
+            CompressionMethod::Unsupported(method) => {
+                return Err(FormatError::UnsupportedCompressionMethod { method }.into())
+            }
+        };
+        Ok(Self {
+            output: if inflater.is_some() {
+                vec![0; OUTPUT_BUFFER_SIZE]
+            } else {
+                Vec::new()
+            },
+            inflater,
+            hasher: crc32fast::Hasher::new(),
+            uncompressed_size: 0,
+            finished: false,
+        })
+    }
+
+    /// Decompresses as much of `input` as possible, passing the output to `sink`.
+    ///
+    /// Returns the number of input bytes consumed. Stored data is always consumed
+    /// entirely, while deflated data is consumed up to the end of the deflate stream.
+    pub(crate) fn decode(
+        &mut self,
+        input: &[u8],
+        mut sink: impl FnMut(&[u8]),
+    ) -> Result<usize, Error> {
+        let inflater = match self.inflater.as_mut() {
+            Some(inflater) => inflater,
+            None => {
+                self.hasher.update(input);
+                self.uncompressed_size += input.len() as u64;
+                sink(input);
+                return Ok(input.len());
+            }
+        };
+
+        let mut consumed = 0;
+        while !self.finished {
+            let result = inflate(
+                inflater,
+                &input[consumed..],
+                &mut self.output,
+                MZFlush::None,
+            );
+            consumed += result.bytes_consumed;
+            let output = &self.output[..result.bytes_written];
+            self.hasher.update(output);
+            self.uncompressed_size += output.len() as u64;
+            sink(output);
+
+            match result.status {
+                Ok(MZStatus::StreamEnd) => self.finished = true,
+                Ok(_) if result.bytes_consumed == 0 && result.bytes_written == 0 => break,
+                Ok(_) => {}
+                // No progress can be made without more input.
+                Err(MZError::Buf) => break,
+                Err(e) => return Err(Error::Decompression(format!("{:?}", e))),
+            }
+        }
+        Ok(consumed)
+    }
+
+    /// Returns true once the end of a deflate stream has been decoded.
+    ///
+    /// Stored data has no end marker, so this is always false for it.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the number of bytes written to the sink so far.
+    pub(crate) fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns the CRC-32 of the bytes written to the sink so far.
+    pub(crate) fn crc32(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+
+    /// Checks the decompressed data against the expected size and CRC-32.
+    pub(crate) fn verify(&self, crc32: u32, uncompressed_size: u64) -> Result<(), Error> {
+        if self.uncompressed_size != uncompressed_size {
+            return Err(FormatError::WrongSize {
+                expected: uncompressed_size,
+                actual: self.uncompressed_size,
+            }
+            .into());
+        }
+        let actual = self.crc32();
+        if actual != crc32 {
+            return Err(FormatError::WrongChecksum {
+                expected: crc32,
+                actual,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decoder")
+            .field("deflated", &self.inflater.is_some())
+            .field("uncompressed_size", &self.uncompressed_size)
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use crate::extra::{ExtraField, ExtraFields};
+
+/// General purpose flag: the filename and comment are encoded in UTF-8.
+pub(crate) const FLAG_UTF8: u16 = 1 << 11;
+
+/// Decodes filenames and comments that are not flagged as UTF-8.
+///
+/// Returns `None` to fall back to CP437.
+pub(crate) type LegacyDecoder = dyn Fn(&[u8]) -> Option<String> + Send + Sync;
+
+/// Decodes the filenames of an archive according to their flags and extra fields.
+#[derive(Default)]
+pub(crate) struct NameDecoder {
+    legacy: Option<Box<LegacyDecoder>>,
+}
+
+impl NameDecoder {
+    pub(crate) fn new(legacy: Box<LegacyDecoder>) -> Self {
+        Self {
+            legacy: Some(legacy),
+        }
+    }
+
+    /// Decodes a filename or comment.
+    ///
+    /// UTF-8 is used when bit 11 of the flags is set, and is then required to be
+    /// valid. Otherwise the Info-ZIP Unicode field for it is used when present and
+    /// still matching, before the legacy decoder, and finally CP437.
+    pub(crate) fn decode(
+        &self,
+        raw: &[u8],
+        flags: u16,
+        unicode_field: Option<&crate::UnicodeExtraField>,
+    ) -> Option<String> {
+        if flags & FLAG_UTF8 != 0 {
+            return String::from_utf8(raw.to_vec()).ok();
+        }
+        if let Some(field) = unicode_field.filter(|field| field.matches(raw)) {
+            return Some(field.text.clone());
+        }
+        Some(
+            self.legacy
+                .as_ref()
+                .and_then(|legacy| legacy(raw))
+                .unwrap_or_else(|| decode_cp437(raw)),
+        )
+    }
+
+    /// Decodes a filename, looking for its Unicode path field in `extra_field`.
+    pub(crate) fn decode_name(&self, raw: &[u8], flags: u16, extra_field: &[u8]) -> Option<String> {
+        let unicode_path = ExtraFields::new(extra_field).find_map(|field| match field {
+            Ok(ExtraField::UnicodePath(path)) => Some(path),
+            _ => None,
+        });
+        self.decode(raw, flags, unicode_path.as_ref())
+    }
+
+    /// Decodes a comment, looking for its Unicode comment field in `extra_field`.
+    ///
+    /// Unlike filenames, comments flagged as UTF-8 but invalid are decoded lossily.
+    pub(crate) fn decode_comment(&self, raw: &[u8], flags: u16, extra_field: &[u8]) -> String {
+        let unicode_comment = ExtraFields::new(extra_field).find_map(|field| match field {
+            Ok(ExtraField::UnicodeComment(comment)) => Some(comment),
+            _ => None,
+        });
+        self.decode(raw, flags, unicode_comment.as_ref())
+            .unwrap_or_else(|| String::from_utf8_lossy(raw).into_owned())
+    }
+}
+
+impl fmt::Debug for NameDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NameDecoder")
+            .field("legacy", &self.legacy.is_some())
+            .finish()
+    }
+}
+
+/// Decodes text in IBM code page 437, the original encoding of zip filenames.
+pub fn decode_cp437(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0..=0x7f => byte as char,
+            _ => CP437_HIGH[byte as usize - 0x80],
+        })
+        .collect()
+}
+
+/// The characters of bytes 0x80 to 0xff in CP437.
+#[rustfmt::skip]
+const CP437_HIGH: [char; 128] = [
+    '\u{00c7}', '\u{00fc}', '\u{00e9}', '\u{00e2}', '\u{00e4}', '\u{00e0}', '\u{00e5}', '\u{00e7}',
+    '\u{00ea}', '\u{00eb}', '\u{00e8}', '\u{00ef}', '\u{00ee}', '\u{00ec}', '\u{00c4}', '\u{00c5}',
+    '\u{00c9}', '\u{00e6}', '\u{00c6}', '\u{00f4}', '\u{00f6}', '\u{00f2}', '\u{00fb}', '\u{00f9}',
+    '\u{00ff}', '\u{00d6}', '\u{00dc}', '\u{00a2}', '\u{00a3}', '\u{00a5}', '\u{20a7}', '\u{0192}',
+    '\u{00e1}', '\u{00ed}', '\u{00f3}', '\u{00fa}', '\u{00f1}', '\u{00d1}', '\u{00aa}', '\u{00ba}',
+    '\u{00bf}', '\u{2310}', '\u{00ac}', '\u{00bd}', '\u{00bc}', '\u{00a1}', '\u{00ab}', '\u{00bb}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{2561}', '\u{2562}', '\u{2556}',
+    '\u{2555}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255d}', '\u{255c}', '\u{255b}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252c}', '\u{251c}', '\u{2500}', '\u{253c}', '\u{255e}', '\u{255f}',
+    '\u{255a}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256c}', '\u{2567}',
+    '\u{2568}', '\u{2564}', '\u{2565}', '\u{2559}', '\u{2558}', '\u{2552}', '\u{2553}', '\u{256b}',
+    '\u{256a}', '\u{2518}', '\u{250c}', '\u{2588}', '\u{2584}', '\u{258c}', '\u{2590}', '\u{2580}',
+    '\u{03b1}', '\u{00df}', '\u{0393}', '\u{03c0}', '\u{03a3}', '\u{03c3}', '\u{00b5}', '\u{03c4}',
+    '\u{03a6}', '\u{0398}', '\u{03a9}', '\u{03b4}', '\u{221e}', '\u{03c6}', '\u{03b5}', '\u{2229}',
+    '\u{2261}', '\u{00b1}', '\u{2265}', '\u{2264}', '\u{2320}', '\u{2321}', '\u{00f7}', '\u{2248}',
+    '\u{00b0}', '\u{2219}', '\u{00b7}', '\u{221a}', '\u{207f}', '\u{00b2}', '\u{25a0}', '\u{00a0}',
+];
@@ -9,6 +9,9 @@ pub enum Error {
     /// Bad header format
     BadHeader,
 
+    /// The compressed data of an entry could not be decompressed.
+    Decompression(String),
+
     /// I/O-related error
     ///
     /// Only returned by the higher-level API, since
@@ -37,7 +40,19 @@ pub enum FormatError {
     /// This can happen when the end of central directory record advertises
     /// a certain number of files, but we weren't able to read the same number of central directory
     /// headers.
-    InvalidCentralRecord { expected: u16, actual: u16 },
+    InvalidCentralRecord { expected: u64, actual: u64 },
+    /// A local entry and its central directory record disagree on its name,
+    /// compression method, sizes or CRC-32, or several records point to it.
+    CentralRecordMismatch { name: String, offset: u64 },
+    /// The end of central directory records locate the central directory, or the
+    /// ZIP64 end of central directory record, elsewhere than where it was read.
+    DirectoryPositionMismatch { expected: u64, actual: u64 },
+    /// Data follows the end of central directory record and its comment.
+    TrailingData { offset: u64 },
+    /// A local entry has no central directory record.
+    MissingCentralRecord { name: String, offset: u64 },
+    /// A central directory record points to no local entry that was read.
+    MissingLocalEntry { name: String, offset: u64 },
     /// An extra field (that we support) was not decoded correctly.
     ///
     /// This can indicate an invalid zip archive, or an implementation error in this crate.
@@ -52,12 +67,16 @@ pub enum FormatError {
     },
     /// The local file header (before the file data) could not be parsed correctly.
     InvalidLocalHeader,
+    /// A central directory record could not be parsed correctly.
+    InvalidCentralDirectoryHeader,
     /// The data descriptor (after the file data) could not be parsed correctly.
     InvalidDataDescriptor,
     /// The uncompressed size didn't match
     WrongSize { expected: u64, actual: u64 },
     /// The CRC-32 checksum didn't match.
     WrongChecksum { expected: u32, actual: u32 },
+    /// The entry uses a compression method this crate can not decompress.
+    UnsupportedCompressionMethod { method: u16 },
 }
 
 impl error::Error for Error {}
@@ -68,6 +87,7 @@ impl fmt::Display for Error {
             Error::IO(e) => write!(f, "IO error: {}", e),
             Error::Format(e) => write!(f, "{:#?}", e),
             Error::BadHeader => write!(f, "Bad header format",),
+            Error::Decompression(e) => write!(f, "Decompression error: {}", e),
         }
     }
 }
@@ -86,7 +106,7 @@ impl From<FormatError> for Error {
 
 impl From<Error> for std::io::Error {
     fn from(val: Error) -> Self {
-        std::io::Error::new(std::io::ErrorKind::Other, val)
+        std::io::Error::other(val)
     }
 }
 
@@ -0,0 +1,457 @@
+use std::time::SystemTime;
+
+use bytes::Buf;
+
+use crate::{
+    datetime::{ntfs_time, unix_time},
+    FormatError,
+};
+
+/// Header ID of the ZIP64 extended information extra field.
+pub(crate) const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+/// Header ID of the NTFS extra field.
+pub(crate) const NTFS_EXTRA_FIELD_ID: u16 = 0x000a;
+
+/// Header ID of the extended timestamp extra field.
+pub(crate) const EXTENDED_TIMESTAMP_EXTRA_FIELD_ID: u16 = 0x5455;
+
+/// Header ID of the Info-ZIP Unix UID/GID extra field.
+pub(crate) const UNIX_OWNER_EXTRA_FIELD_ID: u16 = 0x7875;
+
+/// Header ID of the Info-ZIP Unicode path extra field.
+pub(crate) const UNICODE_PATH_EXTRA_FIELD_ID: u16 = 0x7075;
+
+/// Header ID of the Info-ZIP Unicode comment extra field.
+pub(crate) const UNICODE_COMMENT_EXTRA_FIELD_ID: u16 = 0x6375;
+
+/// Header ID of the WinZip AES encryption extra field.
+pub(crate) const AES_EXTRA_FIELD_ID: u16 = 0x9901;
+
+/// Value of a 32-bit header field whose actual value is stored in the ZIP64 extra field.
+pub(crate) const ZIP64_SIZE: u32 = 0xffff_ffff;
+
+/// Iterates over the `(header id, data)` records of an extra field block.
+///
+/// Trailing bytes too short to hold a record header are ignored, as some writers
+/// use them as padding.
+pub(crate) struct RawExtraFields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> RawExtraFields<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for RawExtraFields<'a> {
+    type Item = Result<(u16, &'a [u8]), FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 4 {
+            return None;
+        }
+        let id = (&self.data[0..2]).get_u16_le();
+        let len = (&self.data[2..4]).get_u16_le() as usize;
+        if self.data.len() - 4 < len {
+            self.data = &[];
+            return Some(Err(FormatError::InvalidExtraField));
+        }
+        let field = &self.data[4..4 + len];
+        self.data = &self.data[4 + len..];
+        Some(Ok((id, field)))
+    }
+}
+
+/// The ZIP64 extended information extra field (0x0001).
+///
+/// A value is only present when the matching field of the header it belongs to
+/// was set to `0xFFFFFFFF` (or `0xFFFF` for the disk number).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Zip64ExtendedInformation {
+    pub uncompressed_size: Option<u64>,
+    pub compressed_size: Option<u64>,
+    pub relative_header_offset: Option<u64>,
+    pub disk_start_number: Option<u32>,
+}
+
+impl Zip64ExtendedInformation {
+    fn parse(data: &[u8], fields: Zip64Fields) -> Result<Self, FormatError> {
+        let mut data = data;
+        let uncompressed_size = read_u64_if(&mut data, fields.uncompressed_size)?;
+        let compressed_size = read_u64_if(&mut data, fields.compressed_size)?;
+        let relative_header_offset = read_u64_if(&mut data, fields.relative_header_offset)?;
+        let disk_start_number = if !fields.disk_start_number {
+            None
+        } else if data.remaining() < 4 {
+            return Err(FormatError::InvalidExtraField);
+        } else {
+            Some(data.get_u32_le())
+        };
+        Ok(Self {
+            uncompressed_size,
+            compressed_size,
+            relative_header_offset,
+            disk_start_number,
+        })
+    }
+
+    /// Returns which values are present, as the header this field belongs to
+    /// would announce them.
+    pub(crate) fn fields(&self) -> Zip64Fields {
+        Zip64Fields {
+            uncompressed_size: self.uncompressed_size.is_some(),
+            compressed_size: self.compressed_size.is_some(),
+            relative_header_offset: self.relative_header_offset.is_some(),
+            disk_start_number: self.disk_start_number.is_some(),
+        }
+    }
+}
+
+/// Which values a ZIP64 extended information extra field holds, which depends on
+/// the header it belongs to.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Zip64Fields {
+    pub(crate) uncompressed_size: bool,
+    pub(crate) compressed_size: bool,
+    pub(crate) relative_header_offset: bool,
+    pub(crate) disk_start_number: bool,
+}
+
+impl Zip64Fields {
+    /// Returns the values present for a local file header, given its 32-bit sizes.
+    pub(crate) fn local(uncompressed_size: u32, compressed_size: u32) -> Self {
+        Self {
+            uncompressed_size: uncompressed_size == ZIP64_SIZE,
+            compressed_size: compressed_size == ZIP64_SIZE,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the values present for a central directory record, given its
+    /// 32-bit sizes and offset and 16-bit disk number.
+    pub(crate) fn central(
+        uncompressed_size: u32,
+        compressed_size: u32,
+        relative_header_offset: u32,
+        disk_start_number: u16,
+    ) -> Self {
+        Self {
+            uncompressed_size: uncompressed_size == ZIP64_SIZE,
+            compressed_size: compressed_size == ZIP64_SIZE,
+            relative_header_offset: relative_header_offset == ZIP64_SIZE,
+            disk_start_number: disk_start_number == 0xffff,
+        }
+    }
+}
+
+/// The extended timestamp extra field (0x5455), written by Info-ZIP and others.
+///
+/// The times are in seconds since 1970-01-01 UTC. Central directory records usually
+/// only carry the modification time, even when the flags announce more.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtendedTimestamp {
+    pub mtime: Option<i32>,
+    pub atime: Option<i32>,
+    pub ctime: Option<i32>,
+}
+
+impl ExtendedTimestamp {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        let mut data = data;
+        if data.is_empty() {
+            return Err(FormatError::InvalidExtraField);
+        }
+        let flags = data.get_u8();
+        let mut read_time = |bit: u8| {
+            if flags & (1 << bit) != 0 && data.remaining() >= 4 {
+                Some(data.get_i32_le())
+            } else {
+                None
+            }
+        };
+        Ok(Self {
+            mtime: read_time(0),
+            atime: read_time(1),
+            ctime: read_time(2),
+        })
+    }
+
+    /// Returns the last modification time.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.mtime.and_then(|time| unix_time(time.into()))
+    }
+
+    /// Returns the last access time.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        self.atime.and_then(|time| unix_time(time.into()))
+    }
+
+    /// Returns the creation time.
+    pub fn created(&self) -> Option<SystemTime> {
+        self.ctime.and_then(|time| unix_time(time.into()))
+    }
+}
+
+/// The timestamps of the NTFS extra field (0x000a), written by Windows tools such
+/// as 7-Zip and WinZip.
+///
+/// The times are in 100 ns intervals since 1601-01-01 UTC.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NtfsTimestamps {
+    pub mtime: u64,
+    pub atime: u64,
+    pub ctime: u64,
+}
+
+impl NtfsTimestamps {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        // Skips the reserved bytes, then looks for the timestamps attribute (tag 1).
+        let mut data = data.get(4..).ok_or(FormatError::InvalidExtraField)?;
+        while data.remaining() >= 4 {
+            let tag = data.get_u16_le();
+            let size = data.get_u16_le() as usize;
+            if data.remaining() < size {
+                break;
+            }
+            if tag == 1 && size >= 24 {
+                return Ok(Self {
+                    mtime: data.get_u64_le(),
+                    atime: data.get_u64_le(),
+                    ctime: data.get_u64_le(),
+                });
+            }
+            data.advance(size);
+        }
+        Err(FormatError::InvalidExtraField)
+    }
+
+    /// Returns the last modification time.
+    pub fn modified(&self) -> Option<SystemTime> {
+        ntfs_time(self.mtime)
+    }
+
+    /// Returns the last access time.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        ntfs_time(self.atime)
+    }
+
+    /// Returns the creation time.
+    pub fn created(&self) -> Option<SystemTime> {
+        ntfs_time(self.ctime)
+    }
+}
+
+/// The Info-ZIP Unix extra field (0x7875), holding the owner of the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnixOwner {
+    pub uid: u64,
+    pub gid: u64,
+}
+
+impl UnixOwner {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        let mut data = data;
+        if data.remaining() < 1 || data.get_u8() != 1 {
+            return Err(FormatError::InvalidExtraField);
+        }
+        Ok(Self {
+            uid: read_var_uint(&mut data)?,
+            gid: read_var_uint(&mut data)?,
+        })
+    }
+}
+
+/// The Info-ZIP Unicode path (0x7075) or comment (0x6375) extra field.
+///
+/// It holds the UTF-8 version of the filename or comment of the header, which only
+/// applies as long as the CRC-32 of that original value still matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnicodeExtraField {
+    /// The CRC-32 of the original filename or comment.
+    pub crc32: u32,
+    pub text: String,
+}
+
+impl UnicodeExtraField {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        let mut data = data;
+        if data.remaining() < 5 || data.get_u8() != 1 {
+            return Err(FormatError::InvalidExtraField);
+        }
+        let crc32 = data.get_u32_le();
+        let text = std::str::from_utf8(data).map_err(|_| FormatError::InvalidExtraField)?;
+        Ok(Self {
+            crc32,
+            text: text.to_owned(),
+        })
+    }
+
+    /// Returns true when the field applies to the given original filename or comment.
+    pub fn matches(&self, original: &[u8]) -> bool {
+        crc32fast::hash(original) == self.crc32
+    }
+}
+
+/// The WinZip AES encryption extra field (0x9901).
+///
+/// The entry data is encrypted, and the actual compression method is stored here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AesExtraField {
+    /// 1 for AE-1, 2 for AE-2.
+    pub version: u16,
+    pub vendor_id: [u8; 2],
+    /// 1, 2 or 3 for 128, 192 or 256 bit keys.
+    pub strength: u8,
+    pub compression_method: u16,
+}
+
+impl AesExtraField {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        let mut data = data;
+        if data.remaining() < 7 {
+            return Err(FormatError::InvalidExtraField);
+        }
+        Ok(Self {
+            version: data.get_u16_le(),
+            vendor_id: [data.get_u8(), data.get_u8()],
+            strength: data.get_u8(),
+            compression_method: data.get_u16_le(),
+        })
+    }
+}
+
+/// A decoded extra field of a header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraField<'a> {
+    /// The ZIP64 extended information (0x0001).
+    Zip64(Zip64ExtendedInformation),
+    /// The extended timestamp (0x5455).
+    ExtendedTimestamp(ExtendedTimestamp),
+    /// The NTFS timestamps (0x000a).
+    Ntfs(NtfsTimestamps),
+    /// The Info-ZIP Unix UID/GID (0x7875).
+    UnixOwner(UnixOwner),
+    /// The Info-ZIP Unicode path (0x7075).
+    UnicodePath(UnicodeExtraField),
+    /// The Info-ZIP Unicode comment (0x6375).
+    UnicodeComment(UnicodeExtraField),
+    /// The WinZip AES encryption field (0x9901).
+    Aes(AesExtraField),
+    /// Any other field, left undecoded.
+    Unknown { id: u16, data: &'a [u8] },
+}
+
+/// Iterates over the decoded fields of an extra field block.
+///
+/// A field that can not be decoded is returned as [FormatError::InvalidExtraField],
+/// after which the remaining fields can still be read. A field whose length runs
+/// past the end of the block ends the iteration after that error.
+pub struct ExtraFields<'a> {
+    raw: RawExtraFields<'a>,
+    /// The values of the ZIP64 field, when known from its header.
+    zip64: Option<Zip64Fields>,
+}
+
+impl<'a> ExtraFields<'a> {
+    /// Iterates over the fields of `extra_field`.
+    ///
+    /// As the values of a ZIP64 field depend on its header, they are read in order
+    /// for as long as the field goes. The `extra_fields` accessors of the headers
+    /// decode it according to the header instead.
+    pub fn new(extra_field: &'a [u8]) -> Self {
+        Self {
+            raw: RawExtraFields::new(extra_field),
+            zip64: None,
+        }
+    }
+
+    pub(crate) fn with_zip64_fields(extra_field: &'a [u8], zip64: Zip64Fields) -> Self {
+        Self {
+            raw: RawExtraFields::new(extra_field),
+            zip64: Some(zip64),
+        }
+    }
+}
+
+impl<'a> Iterator for ExtraFields<'a> {
+    type Item = Result<ExtraField<'a>, FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, data) = match self.raw.next()? {
+            Ok(field) => field,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(match id {
+            ZIP64_EXTRA_FIELD_ID => {
+                let fields = self.zip64.unwrap_or(Zip64Fields {
+                    uncompressed_size: data.len() >= 8,
+                    compressed_size: data.len() >= 16,
+                    relative_header_offset: data.len() >= 24,
+                    disk_start_number: data.len() >= 28,
+                });
+                Zip64ExtendedInformation::parse(data, fields).map(ExtraField::Zip64)
+            }
+            EXTENDED_TIMESTAMP_EXTRA_FIELD_ID => {
+                ExtendedTimestamp::parse(data).map(ExtraField::ExtendedTimestamp)
+            }
+            NTFS_EXTRA_FIELD_ID => NtfsTimestamps::parse(data).map(ExtraField::Ntfs),
+            UNIX_OWNER_EXTRA_FIELD_ID => UnixOwner::parse(data).map(ExtraField::UnixOwner),
+            UNICODE_PATH_EXTRA_FIELD_ID => {
+                UnicodeExtraField::parse(data).map(ExtraField::UnicodePath)
+            }
+            UNICODE_COMMENT_EXTRA_FIELD_ID => {
+                UnicodeExtraField::parse(data).map(ExtraField::UnicodeComment)
+            }
+            AES_EXTRA_FIELD_ID => AesExtraField::parse(data).map(ExtraField::Aes),
+            id => Ok(ExtraField::Unknown { id, data }),
+        })
+    }
+}
+
+/// Finds the ZIP64 extra field of a header and decodes it, given the values the
+/// header announces.
+pub(crate) fn zip64_field(
+    extra_field: &[u8],
+    fields: Zip64Fields,
+) -> Result<Option<Zip64ExtendedInformation>, FormatError> {
+    let mut raw = RawExtraFields::new(extra_field);
+    loop {
+        let rest = raw.data;
+        match raw.next() {
+            Some(Ok((ZIP64_EXTRA_FIELD_ID, data))) => {
+                return Zip64ExtendedInformation::parse(data, fields).map(Some);
+            }
+            Some(Ok(_)) => {}
+            // Only a truncated ZIP64 record is an error here: past any other one,
+            // the records can not be found anymore.
+            Some(Err(e)) if (&rest[0..2]).get_u16_le() == ZIP64_EXTRA_FIELD_ID => {
+                return Err(e);
+            }
+            Some(Err(_)) | None => return Ok(None),
+        }
+    }
+}
+
+/// Reads a little-endian integer preceded by its size in bytes, of up to 8 bytes.
+fn read_var_uint(data: &mut &[u8]) -> Result<u64, FormatError> {
+    if data.remaining() < 1 {
+        return Err(FormatError::InvalidExtraField);
+    }
+    let size = data.get_u8() as usize;
+    if size > 8 || data.remaining() < size {
+        return Err(FormatError::InvalidExtraField);
+    }
+    Ok(data.get_uint_le(size))
+}
+
+fn read_u64_if(data: &mut &[u8], present: bool) -> Result<Option<u64>, FormatError> {
+    if !present {
+        return Ok(None);
+    }
+    if data.remaining() < 8 {
+        return Err(FormatError::InvalidExtraField);
+    }
+    Ok(Some(data.get_u64_le()))
+}
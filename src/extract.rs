@@ -0,0 +1,254 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::EntryKind;
+
+/// Size of the chunks of decompressed data written at a time.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// What was done with an entry when extracting an archive to a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedEntry {
+    /// The filename of the entry.
+    pub name: String,
+    /// Where the entry was extracted, or would have been. `None` when the name is
+    /// not a safe relative path.
+    pub path: Option<PathBuf>,
+    pub outcome: ExtractOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractOutcome {
+    /// A file was written with `size` bytes of decompressed data.
+    File { size: u64 },
+    /// A directory was created, or already existed.
+    Directory,
+    /// The entry was not extracted, as its name is absolute, has a drive prefix
+    /// or goes up with `..`.
+    UnsafePath,
+    /// The entry was not extracted, as its path goes through an existing symbolic
+    /// link that points outside of the target directory.
+    SymlinkEscape,
+    /// The entry could not be written, for instance because a file is in the way of
+    /// its directory. What was written of it is left in place.
+    Failed(io::ErrorKind),
+    /// The entry could not be read from the archive, for instance because its data
+    /// does not match its CRC-32. What was written of it is removed, and the
+    /// extraction stops there, as the rest of the archive can not be read.
+    ReadFailed(io::ErrorKind),
+}
+
+/// Turns the name of an entry into a relative path below the target directory.
+///
+/// Both `/` and `\` are taken as separators. Returns `None` for names that are
+/// empty, absolute, start with a drive prefix or have a `..` component.
+pub(crate) fn relative_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for (i, part) in name.split(['/', '\\']).enumerate() {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            _ if i == 0 && has_drive_prefix(part) => return None,
+            _ => path.push(part),
+        }
+    }
+    let is_relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (is_relative && !path.as_os_str().is_empty()).then_some(path)
+}
+
+fn has_drive_prefix(part: &str) -> bool {
+    let part = part.as_bytes();
+    part.len() >= 2 && part[0].is_ascii_alphabetic() && part[1] == b':'
+}
+
+/// Creates the target directory if needed, and returns its canonical path.
+pub(crate) fn prepare_target(dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    fs::canonicalize(dir)
+}
+
+/// Same as [prepare_target], with `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub(crate) async fn prepare_target_async(dir: &Path) -> io::Result<PathBuf> {
+    tokio::fs::create_dir_all(dir).await?;
+    tokio::fs::canonicalize(dir).await
+}
+
+/// Extracts an entry below `target`, which has to be canonical.
+///
+/// Failures to write the entry, as well as to read it from the archive, are
+/// reported in its outcome.
+pub(crate) fn extract_entry(
+    target: &Path,
+    name: String,
+    kind: EntryKind,
+    entry: &mut impl Read,
+) -> ExtractedEntry {
+    let Some(relative) = relative_path(&name) else {
+        return unsafe_path(name);
+    };
+    let outcome = match place_entry(target, &relative, kind) {
+        Ok(path) => write_file(&path, entry),
+        Err(outcome) => outcome,
+    };
+    ExtractedEntry {
+        name,
+        path: Some(target.join(relative)),
+        outcome,
+    }
+}
+
+fn unsafe_path(name: String) -> ExtractedEntry {
+    ExtractedEntry {
+        name,
+        path: None,
+        outcome: ExtractOutcome::UnsafePath,
+    }
+}
+
+/// Returns the path to write a file entry to, creating the directories leading to
+/// it, or the outcome of the entry when there is nothing to write.
+fn place_entry(target: &Path, relative: &Path, kind: EntryKind) -> Result<PathBuf, ExtractOutcome> {
+    let is_dir = kind == EntryKind::Directory;
+    match resolve(target, relative, is_dir) {
+        Ok(None) => Err(ExtractOutcome::SymlinkEscape),
+        Ok(Some(_)) if is_dir => Err(ExtractOutcome::Directory),
+        Ok(Some(path)) => Ok(path),
+        Err(e) => Err(ExtractOutcome::Failed(e.kind())),
+    }
+}
+
+fn write_file(path: &Path, entry: &mut impl Read) -> ExtractOutcome {
+    let mut file = match File::create(path) {
+        Ok(file) => file,
+        Err(e) => return ExtractOutcome::Failed(e.kind()),
+    };
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+    loop {
+        let len = match entry.read(&mut buf) {
+            Ok(len) => len,
+            Err(e) => {
+                // The data written so far has not been verified.
+                drop(file);
+                let _ = fs::remove_file(path);
+                return ExtractOutcome::ReadFailed(e.kind());
+            }
+        };
+        if len == 0 {
+            return ExtractOutcome::File { size };
+        }
+        if let Err(e) = file.write_all(&buf[..len]) {
+            return ExtractOutcome::Failed(e.kind());
+        }
+        size += len as u64;
+    }
+}
+
+/// Same as [extract_entry], with `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub(crate) async fn extract_entry_async(
+    target: &Path,
+    name: String,
+    kind: EntryKind,
+    entry: &mut (impl tokio::io::AsyncRead + Unpin),
+) -> ExtractedEntry {
+    let Some(relative) = relative_path(&name) else {
+        return unsafe_path(name);
+    };
+    // The directories are checked and created with the same blocking code.
+    let placed = {
+        let (target, relative) = (target.to_path_buf(), relative.clone());
+        tokio::task::spawn_blocking(move || place_entry(&target, &relative, kind)).await
+    };
+    let outcome = match placed {
+        Ok(Ok(path)) => write_file_async(&path, entry).await,
+        Ok(Err(outcome)) => outcome,
+        Err(e) => ExtractOutcome::Failed(io::Error::from(e).kind()),
+    };
+    ExtractedEntry {
+        name,
+        path: Some(target.join(relative)),
+        outcome,
+    }
+}
+
+#[cfg(feature = "tokio")]
+async fn write_file_async(
+    path: &Path,
+    entry: &mut (impl tokio::io::AsyncRead + Unpin),
+) -> ExtractOutcome {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut file = match tokio::fs::File::create(path).await {
+        Ok(file) => file,
+        Err(e) => return ExtractOutcome::Failed(e.kind()),
+    };
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+    loop {
+        let len = match entry.read(&mut buf).await {
+            Ok(len) => len,
+            Err(e) => {
+                drop(file);
+                let _ = tokio::fs::remove_file(path).await;
+                return ExtractOutcome::ReadFailed(e.kind());
+            }
+        };
+        if len == 0 {
+            break;
+        }
+        if let Err(e) = file.write_all(&buf[..len]).await {
+            return ExtractOutcome::Failed(e.kind());
+        }
+        size += len as u64;
+    }
+    // Writes to a tokio file complete in the background.
+    match file.flush().await {
+        Ok(()) => ExtractOutcome::File { size },
+        Err(e) => ExtractOutcome::Failed(e.kind()),
+    }
+}
+
+/// Creates the directories leading to `relative` below `target`, and the last one
+/// too when `is_dir` is true.
+///
+/// Returns the path of the entry, or `None` when an existing symbolic link on the
+/// way, or at the path itself, points outside of `target`. Fails when a file is in
+/// the way of one of the directories.
+fn resolve(target: &Path, relative: &Path, is_dir: bool) -> io::Result<Option<PathBuf>> {
+    let mut path = target.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        path.push(component);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                // A dangling link can not be checked, and would be followed when
+                // creating the file.
+                match fs::canonicalize(&path) {
+                    Ok(real) if real.starts_with(target) => {}
+                    _ => return Ok(None),
+                }
+            }
+            Ok(metadata) if !metadata.is_dir() && (components.peek().is_some() || is_dir) => {
+                return Err(io::ErrorKind::NotADirectory.into());
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if components.peek().is_some() || is_dir {
+                    fs::create_dir(&path)?;
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(Some(path))
+}
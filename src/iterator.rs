@@ -1,9 +1,12 @@
-use crate::{ZipEntry, ZipReader};
+use crate::{Error, LocalFileHeader, ZipEntry, ZipReader};
 
 /// Reads the given file in chunks of N bytes and returns one `ZipEntry` at a time
+///
+/// The iterator ends after the first error.
 pub struct ZipIterator<F, const N: usize> {
     file: F,
     zip_reader: ZipReader,
+    done: bool,
 }
 
 impl<F, const N: usize> ZipIterator<F, N> {
@@ -11,8 +14,29 @@ impl<F, const N: usize> ZipIterator<F, N> {
         Self {
             file,
             zip_reader: ZipReader::default(),
+            done: false,
         }
     }
+
+    /// Only returns the entries whose local header matches `filter`, skipping the
+    /// others without buffering them. See [ZipReader::with_filter].
+    pub fn with_filter(
+        mut self,
+        filter: impl FnMut(&LocalFileHeader) -> bool + Send + 'static,
+    ) -> Self {
+        self.zip_reader = self.zip_reader.with_filter(filter);
+        self
+    }
+
+    /// Decodes the filenames that are not flagged as UTF-8 with `decoder`. See
+    /// [ZipReader::with_legacy_encoding].
+    pub fn with_legacy_encoding(
+        mut self,
+        decoder: impl Fn(&[u8]) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.zip_reader = self.zip_reader.with_legacy_encoding(decoder);
+        self
+    }
 }
 
 impl<F, const N: usize> From<F> for ZipIterator<F, N>
@@ -24,26 +48,40 @@ where
     }
 }
 
+impl<F, const N: usize> ZipIterator<F, N>
+where
+    F: std::io::Read,
+{
+    fn read_chunk(&mut self) -> Result<(), Error> {
+        let mut buf = [0u8; N];
+        let num = self.file.read(&mut buf)?;
+
+        if num == 0 {
+            self.done = true;
+            return self.zip_reader.finish();
+        }
+
+        self.zip_reader.update(buf[..num].to_vec().into())
+    }
+}
+
 impl<F, const N: usize> Iterator for ZipIterator<F, N>
 where
     F: std::io::Read,
 {
-    type Item = ZipEntry;
+    type Item = Result<ZipEntry, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             match self.zip_reader.take_entry() {
+                None if self.done => return self.zip_reader.take_error().map(Err),
                 None => {
-                    let mut buf = [0u8; N];
-                    let num = self.file.read(&mut buf).unwrap();
-
-                    if num == 0 {
-                        return None;
+                    if let Err(e) = self.read_chunk() {
+                        self.done = true;
+                        return Some(Err(e));
                     }
-
-                    self.zip_reader.update(buf[..num].to_vec().into());
                 }
-                entry => return entry,
+                entry => return entry.map(Ok),
             }
         }
     }
@@ -1,9 +1,31 @@
+mod attributes;
+pub use attributes::{
+    EntryKind, DOS_ARCHIVE, DOS_DIRECTORY, DOS_HIDDEN, DOS_READ_ONLY, DOS_SYSTEM,
+};
+mod datetime;
+pub use datetime::DosDateTime;
+mod decoder;
+mod encoding;
+pub use encoding::decode_cp437;
 mod error;
 pub use self::error::*;
+mod extract;
+pub use extract::{ExtractOutcome, ExtractedEntry};
+mod extra;
+pub use extra::{
+    AesExtraField, ExtendedTimestamp, ExtraField, ExtraFields, NtfsTimestamps, UnicodeExtraField,
+    UnixOwner, Zip64ExtendedInformation,
+};
 mod reader;
 pub use reader::*;
 mod iterator;
 pub use iterator::*;
+mod stream_reader;
+pub use stream_reader::*;
+#[cfg(feature = "tokio")]
+mod stream;
+#[cfg(feature = "tokio")]
+pub use stream::*;
 
 #[cfg(test)]
 mod tests {
@@ -24,7 +46,13 @@ mod tests {
         let mut expected = zip::ZipArchive::new(f2).unwrap();
         let mut f = tokio::fs::File::from_std(f);
         let mut buff: [u8; 10000] = [0; 10000];
-        let mut zip_reader = ZipReader::default();
+        // The comment of readme.zip is one byte longer than its declared length,
+        // which validation rejects as trailing data.
+        let mut zip_reader = if path.ends_with("readme.zip") {
+            ZipReader::default()
+        } else {
+            ZipReader::default().with_validation()
+        };
         while let Ok(num) = f.read(&mut buff).await {
             if num == 0 {
                 break;
@@ -37,13 +65,22 @@ mod tests {
                 } else {
                     buffer_size
                 };
-                zip_reader.update(buff[last..(last + to_read)].to_vec().into());
+                zip_reader.update(buff[last..(last + to_read)].to_vec().into())?;
                 last += to_read;
                 left_to_read -= to_read;
             }
         }
-        zip_reader.finish();
+        zip_reader.finish()?;
         println!("found {} zip entries", zip_reader.entries().len());
+        assert_eq!(zip_reader.central_directory().len(), expected.len());
+        for record in zip_reader.central_directory() {
+            let expected_entry = expected.by_name(&record.filename)?;
+            assert_eq!(expected_entry.compressed_size(), record.compressed_size);
+            assert_eq!(
+                expected_entry.header_start(),
+                record.relative_offset_of_local_header
+            );
+        }
         let expanded = zip_reader
             .drain_entries()
             .into_iter()
@@ -59,8 +96,13 @@ mod tests {
             // println!("File compressed size: {:?}", entry.compressed_size());
             let mut expected_entry = expected.by_name(entry.name()).unwrap();
             assert_eq!(expected_entry.size(), entry.uncompressed_size() as _);
-            let mut expected_bytes = Vec::new();
-            expected_bytes.resize(expected_entry.size() as _, 0);
+            assert_eq!(expected_entry.crc32(), entry.crc32());
+            let modified = expected_entry.last_modified();
+            assert_eq!(
+                (modified.datepart(), modified.timepart()),
+                (entry.last_modified().date, entry.last_modified().time)
+            );
+            let mut expected_bytes = vec![0; expected_entry.size() as _];
             expected_entry.read_exact(&mut expected_bytes).unwrap();
             assert_eq!(expected_bytes, entry.data().to_vec());
         }
@@ -86,6 +128,125 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_stream() -> anyhow::Result<()> {
+        use futures::StreamExt;
+
+        let file = tokio::fs::File::open(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip"),
+        )
+        .await?;
+        let entries = ZipStream::with_capacity(32, file).collect::<Vec<_>>().await;
+
+        assert_eq!(2, entries.len());
+        for entry in entries {
+            entry?.inflate()?;
+        }
+
+        let mut stream = ZipStream::new(&b"not a zip file"[..]);
+        assert!(matches!(stream.next().await, Some(Err(Error::BadHeader))));
+        assert!(stream.next().await.is_none());
+
+        let bytes = entries_then_junk()?;
+        let entries = ZipStream::new(bytes.as_slice()).collect::<Vec<_>>().await;
+        assert_eq!(entries.len(), 3);
+        assert!(entries[..2].iter().all(|entry| entry.is_ok()));
+        assert!(matches!(entries[2], Err(Error::BadHeader)));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_entries() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip");
+        let mut expected = zip::ZipArchive::new(File::open(&path)?)?;
+        let file = tokio::fs::File::open(&path).await?;
+        let mut archive = AsyncZipStreamReader::with_capacity(32, file);
+
+        let mut names = Vec::new();
+        while let Some(mut entry) = archive.next_entry().await? {
+            names.push(entry.name().to_owned());
+            let mut expected_bytes = Vec::new();
+            expected
+                .by_name(entry.name())?
+                .read_to_end(&mut expected_bytes)?;
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).await?;
+            assert_eq!(expected_bytes, data);
+        }
+        assert_eq!(names, ["test.txt", "gophercolor16x16.png"]);
+
+        // Entries that are not read, or only partially, are skipped.
+        let file = tokio::fs::File::open(&path).await?;
+        let mut archive = AsyncZipStreamReader::with_capacity(32, file);
+        let mut entry = archive.next_entry().await?.unwrap();
+        let mut start = [0; 4];
+        entry.read_exact(&mut start).await?;
+        assert_eq!(&start, b"This");
+        assert_eq!(
+            archive.next_entry().await?.unwrap().name(),
+            "gophercolor16x16.png"
+        );
+        assert!(archive.next_entry().await?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_reader() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip");
+        let mut expected = zip::ZipArchive::new(File::open(&path)?)?;
+        let mut archive = ZipStreamReader::with_capacity(32, File::open(&path)?);
+
+        let mut names = Vec::new();
+        while let Some(mut entry) = archive.next_entry()? {
+            names.push(entry.name().to_owned());
+            let mut expected_bytes = Vec::new();
+            expected
+                .by_name(entry.name())?
+                .read_to_end(&mut expected_bytes)?;
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            assert_eq!(expected_bytes, data);
+        }
+        assert_eq!(names, ["test.txt", "gophercolor16x16.png"]);
+
+        // Entries that are not read, or only partially, are skipped.
+        let mut archive = ZipStreamReader::with_capacity(32, File::open(&path)?);
+        let mut entry = archive.next_entry()?.unwrap();
+        let mut start = [0; 4];
+        entry.read_exact(&mut start)?;
+        assert_eq!(&start, b"This");
+        assert_eq!(
+            archive.next_entry()?.unwrap().name(),
+            "gophercolor16x16.png"
+        );
+        assert!(archive.next_entry()?.is_none());
+
+        let mut archive = ZipStreamReader::new(&b"not a zip file"[..]);
+        assert!(matches!(archive.next_entry(), Err(Error::BadHeader)));
+        assert!(archive.next_entry()?.is_none());
+
+        // Entries before a corrupt one are given out before the error.
+        let mut bytes = archive_of(
+            &[("good.txt", b"good"), ("bad.txt", b"bad")],
+            zip::CompressionMethod::Stored,
+        )?;
+        let bad = bytes
+            .windows(10)
+            .position(|window| window == b"bad.txtbad")
+            .unwrap();
+        bytes[bad + 7] = b'm';
+        let mut archive = ZipStreamReader::new(bytes.as_slice());
+        let mut entry = archive.next_entry()?.unwrap();
+        let mut data = String::new();
+        entry.read_to_string(&mut data)?;
+        assert_eq!(data, "good");
+        let mut entry = archive.next_entry()?.unwrap();
+        assert!(entry.read_to_string(&mut data).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_iter() {
         let file = std::fs::File::open(
@@ -96,9 +257,1256 @@ mod tests {
 
         let mut entries = Vec::new();
         for entry in zip {
-            entries.push(entry);
+            entries.push(entry.unwrap());
         }
 
         assert_eq!(1, entries.len());
+        assert_eq!(entries[0].name(), "README");
+        let modified = entries[0].last_modified();
+        assert_eq!(
+            (modified.year(), modified.month(), modified.day()),
+            (2010, 9, 2)
+        );
+        assert_eq!((modified.hour(), modified.minute()), (11, 57));
+    }
+
+    /// Returns the entries of test.zip followed by junk instead of the central directory.
+    fn entries_then_junk() -> anyhow::Result<Vec<u8>> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip");
+        let mut bytes = std::fs::read(path)?;
+        let central_directory = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .unwrap();
+        bytes.truncate(central_directory);
+        bytes.extend_from_slice(b"not a header");
+        Ok(bytes)
+    }
+
+    #[test]
+    fn test_iter_error_after_entries() -> anyhow::Result<()> {
+        // The entries completed before the error are returned first.
+        let bytes = entries_then_junk()?;
+        let entries = ZipIterator::<_, 4096>::new(bytes.as_slice()).collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().name(), "test.txt");
+        assert_eq!(entries[1].as_ref().unwrap().name(), "gophercolor16x16.png");
+        assert!(matches!(entries[2], Err(Error::BadHeader)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_iter_ends_after_error() -> anyhow::Result<()> {
+        // A deflated entry whose data descriptor is cut short.
+        let data = miniz_oxide::deflate::compress_to_vec(b"a", 6);
+        let mut bytes = local_file("ok.txt", 0, 0, (crc32fast::hash(b"ok"), 2, 2), &[], b"ok");
+        bytes.extend(local_file("a.txt", 0x08, 8, (0, 0, 0), &[], &data));
+        bytes.extend(b"PK\x07\x08\x01\x02");
+
+        let entries = ZipIterator::<_, 4096>::new(bytes.as_slice()).collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].as_ref().unwrap().name(), "ok.txt");
+        assert!(
+            matches!(&entries[1], Err(Error::IO(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+        );
+
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        assert!(zip_reader.finish().is_ok());
+        assert_eq!(zip_reader.take_entry().unwrap().name(), "ok.txt");
+        assert!(zip_reader.take_entry().is_none());
+        assert!(zip_reader.take_error().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_leading_data() -> anyhow::Result<()> {
+        // Data in front of the archive is skipped, as with self-extracting archives.
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip");
+        let mut bytes = b"#!/bin/sh\nexit 0\n".to_vec();
+        bytes.extend(std::fs::read(path)?);
+        let names = ZipIterator::<_, 8>::new(bytes.as_slice())
+            .map(|entry| Ok(entry?.name().to_owned()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(names, ["test.txt", "gophercolor16x16.png"]);
+        Ok(())
+    }
+
+    /// Builds an archive of small files compressed with `method`, with their names
+    /// written as given.
+    fn archive_of(
+        files: &[(&str, &[u8])],
+        method: zip::CompressionMethod,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default().compression_method(method);
+        for (name, contents) in files {
+            writer.start_file(*name, options)?;
+            std::io::Write::write_all(&mut writer, contents)?;
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+
+    fn single_entry_zip(
+        name: &str,
+        contents: &[u8],
+        method: zip::CompressionMethod,
+    ) -> anyhow::Result<Vec<u8>> {
+        archive_of(&[(name, contents)], method)
+    }
+
+    #[test]
+    fn test_unsupported_compression() -> anyhow::Result<()> {
+        let bytes = single_entry_zip("bzip2.txt", b"not deflated", zip::CompressionMethod::Bzip2)?;
+
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let entry = zip_reader.take_entry().unwrap();
+
+        assert_eq!(entry.name(), "bzip2.txt");
+        assert_eq!(entry.uncompressed_size(), 12);
+        assert_eq!(entry.crc32(), crc32fast::hash(b"not deflated"));
+        assert_eq!(
+            entry.compression_method(),
+            CompressionMethod::Unsupported(12)
+        );
+        assert!(matches!(
+            entry.inflate(),
+            Err(Error::Format(FormatError::UnsupportedCompressionMethod {
+                method: 12
+            }))
+        ));
+
+        // When streaming, such entries can be skipped, and only fail once read.
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, method) in [
+            ("x.bz2", zip::CompressionMethod::Bzip2),
+            ("want.txt", zip::CompressionMethod::Deflated),
+        ] {
+            let options = zip::write::FileOptions::default().compression_method(method);
+            writer.start_file(name, options)?;
+            std::io::Write::write_all(&mut writer, b"contents")?;
+        }
+        let bytes = writer.finish()?.into_inner();
+        let mut archive = ZipStreamReader::new(bytes.as_slice());
+        archive.next_entry()?.unwrap().skip();
+        let mut entry = archive.next_entry()?.unwrap();
+        assert_eq!(entry.name(), "want.txt");
+        let mut data = String::new();
+        entry.read_to_string(&mut data)?;
+        assert_eq!(data, "contents");
+
+        let mut archive = ZipStreamReader::new(bytes.as_slice());
+        let mut entry = archive.next_entry()?.unwrap();
+        assert!(entry.read_to_string(&mut data).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify() -> anyhow::Result<()> {
+        let contents = b"some stored contents";
+        let data_start = 30 + "stored.txt".len();
+
+        let mut corrupted =
+            single_entry_zip("stored.txt", contents, zip::CompressionMethod::Stored)?;
+        corrupted[data_start] ^= 0xff;
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(corrupted.into())?;
+        zip_reader.finish()?;
+        assert!(matches!(
+            zip_reader.take_entry().unwrap().inflate(),
+            Err(Error::Format(FormatError::WrongChecksum { .. }))
+        ));
+
+        let mut wrong_size =
+            single_entry_zip("stored.txt", contents, zip::CompressionMethod::Stored)?;
+        wrong_size[22] += 1;
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(wrong_size.into())?;
+        zip_reader.finish()?;
+        assert!(matches!(
+            zip_reader.take_entry().unwrap().inflate(),
+            Err(Error::Format(FormatError::WrongSize {
+                expected: 21,
+                actual: 20
+            }))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed() -> anyhow::Result<()> {
+        // Leading data is skipped, so this is only known at the end.
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(b"not a zip file".to_vec().into())?;
+        assert!(matches!(zip_reader.finish(), Err(Error::BadHeader)));
+
+        let bytes = single_entry_zip("stored.txt", b"contents", zip::CompressionMethod::Stored)?;
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes[..20].to_vec().into())?;
+        assert!(matches!(zip_reader.finish(), Err(Error::BadHeader)));
+
+        // Names flagged as UTF-8 have to be valid.
+        let mut invalid_name = bytes.clone();
+        invalid_name[7] |= 0x08;
+        invalid_name[30] = 0xff;
+        let mut zip = ZipIterator::<_, 32>::new(std::io::Cursor::new(invalid_name));
+        assert!(matches!(
+            zip.next(),
+            Some(Err(Error::Format(FormatError::InvalidLocalHeader)))
+        ));
+        assert!(zip.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip64_local_header() -> anyhow::Result<()> {
+        let contents = b"stored in a zip64 entry";
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(true);
+        writer.start_file("large.txt", options)?;
+        std::io::Write::write_all(&mut writer, contents)?;
+        let bytes = writer.finish()?.into_inner();
+
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let entry = zip_reader.take_entry().unwrap();
+
+        let zip64 = entry.header().zip64.clone().unwrap();
+        assert_eq!(zip64.uncompressed_size, Some(contents.len() as u64));
+        assert_eq!(zip64.compressed_size, Some(entry.header().compressed_size));
+        assert_eq!(
+            entry.compressed_data().len() as u64,
+            entry.header().compressed_size
+        );
+        let inflated = entry.inflate()?;
+        assert_eq!(inflated.uncompressed_size(), contents.len() as u64);
+        assert_eq!(inflated.data().as_ref(), contents);
+        Ok(())
+    }
+
+    /// Builds a local file header with the given values, followed by `data`.
+    fn local_file(
+        name: &str,
+        flags: u16,
+        method: u16,
+        (crc32, compressed_size, uncompressed_size): (u32, u32, u32),
+        extra_field: &[u8],
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = b"PK\x03\x04".to_vec();
+        bytes.extend(20u16.to_le_bytes());
+        bytes.extend(flags.to_le_bytes());
+        bytes.extend(method.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(crc32.to_le_bytes());
+        bytes.extend(compressed_size.to_le_bytes());
+        bytes.extend(uncompressed_size.to_le_bytes());
+        bytes.extend((name.len() as u16).to_le_bytes());
+        bytes.extend((extra_field.len() as u16).to_le_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.extend(extra_field);
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_malformed_extra_field() -> anyhow::Result<()> {
+        let contents = b"hello";
+        let crc32 = crc32fast::hash(contents);
+        let sizes = (crc32, contents.len() as u32, contents.len() as u32);
+
+        // A record declaring 16 bytes with only 2 present hides the ones after it,
+        // but the entry does not need a ZIP64 field.
+        let extra_field = [0x34, 0x12, 16, 0, 0, 0];
+        let bytes = local_file("a.txt", 0, 0, sizes, &extra_field, contents);
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let entry = zip_reader.take_entry().unwrap();
+        assert_eq!(entry.header().zip64, None);
+        assert_eq!(entry.compressed_data().as_ref(), contents);
+
+        // The same goes wrong when the truncated record is the ZIP64 one.
+        let extra_field = [0x01, 0x00, 16, 0, 0, 0];
+        let bytes = local_file("a.txt", 0, 0, sizes, &extra_field, contents);
+        let mut zip_reader = ZipReader::default();
+        let result = zip_reader.update(bytes.into());
+        assert!(matches!(
+            result,
+            Err(Error::Format(FormatError::InvalidExtraField))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip64_data_descriptor() -> anyhow::Result<()> {
+        let contents = b"streamed zip64 entry";
+        let crc32 = crc32fast::hash(contents);
+        let size = contents.len() as u64;
+
+        let mut zip64_field = vec![0x01, 0x00, 16, 0];
+        zip64_field.extend(size.to_le_bytes());
+        zip64_field.extend(size.to_le_bytes());
+        let mut bytes = local_file(
+            "zip64.txt",
+            0x08,
+            0,
+            (0, u32::MAX, u32::MAX),
+            &zip64_field,
+            contents,
+        );
+        bytes.extend(b"PK\x07\x08");
+        bytes.extend(crc32.to_le_bytes());
+        bytes.extend(size.to_le_bytes());
+        bytes.extend(size.to_le_bytes());
+        bytes.extend(local_file(
+            "next.txt",
+            0,
+            0,
+            (crc32, size as u32, size as u32),
+            &[],
+            contents,
+        ));
+
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let entries = zip_reader.drain_entries();
+        assert_eq!(entries.len(), 2);
+        for entry in entries {
+            assert_eq!(entry.header().crc32, crc32);
+            assert_eq!(entry.inflate()?.data().as_ref(), contents);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_descriptor_without_sizes() -> anyhow::Result<()> {
+        // Stored data containing a descriptor signature that does not end it.
+        let stored = b"stored PK\x07\x08 data".to_vec();
+        let deflated = b"deflated data, deflated data, deflated data".to_vec();
+        let compressed = miniz_oxide::deflate::compress_to_vec(&deflated, 6);
+
+        let mut bytes = Vec::new();
+        for (name, method, data, contents, signed) in [
+            ("stored.txt", 0, &stored, &stored, true),
+            ("deflated.txt", 8, &compressed, &deflated, true),
+            ("unsigned.txt", 8, &compressed, &deflated, false),
+        ] {
+            bytes.extend(local_file(name, 0x08, method, (0, 0, 0), &[], data));
+            if signed {
+                bytes.extend(b"PK\x07\x08");
+            }
+            bytes.extend(crc32fast::hash(contents).to_le_bytes());
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend((contents.len() as u32).to_le_bytes());
+        }
+
+        let mut zip_reader = ZipReader::default();
+        for byte in bytes {
+            zip_reader.update(vec![byte].into())?;
+        }
+        zip_reader.finish()?;
+        let entries = zip_reader.drain_entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].compressed_data().as_ref(), stored);
+        for (entry, contents) in entries.into_iter().zip([&stored, &deflated, &deflated]) {
+            assert_eq!(entry.inflate()?.data().as_ref(), contents);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming() -> anyhow::Result<()> {
+        let mut files =
+            std::fs::read_dir(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata"))?;
+
+        while let Some(Ok(file)) = files.next() {
+            if file.path().extension() != Some("zip".as_ref()) {
+                continue;
+            }
+            let bytes = std::fs::read(file.path())?;
+            let mut expected = zip::ZipArchive::new(std::io::Cursor::new(&bytes))?;
+
+            let mut zip_reader = ZipReader::streaming();
+            let mut events = Vec::new();
+            for chunk in bytes.chunks(7) {
+                zip_reader.update(chunk.to_vec().into())?;
+                events.extend(zip_reader.drain_events());
+            }
+            zip_reader.finish()?;
+            events.extend(zip_reader.drain_events());
+            assert!(zip_reader.take_entry().is_none());
+
+            let mut count = 0;
+            let mut events = events.into_iter();
+            while let Some(ZipEvent::EntryStart(header)) = events.next() {
+                let mut data = Vec::new();
+                for event in events.by_ref() {
+                    match event {
+                        ZipEvent::Data(chunk) => data.extend(chunk),
+                        ZipEvent::EntryEnd(_) => break,
+                        event => panic!("unexpected event {:?}", event),
+                    }
+                }
+                let mut expected_entry = expected.by_name(&header.filename)?;
+                let mut expected_bytes = Vec::new();
+                expected_entry.read_to_end(&mut expected_bytes)?;
+                assert_eq!(expected_bytes, data);
+                count += 1;
+            }
+            assert_eq!(count, expected.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_chunks() -> anyhow::Result<()> {
+        let contents = (0..100_000u32)
+            .flat_map(|i| i.to_le_bytes())
+            .collect::<Vec<_>>();
+        let bytes = single_entry_zip("large.bin", &contents, zip::CompressionMethod::Deflated)?;
+
+        let mut zip_reader = ZipReader::streaming();
+        zip_reader.update(bytes[..bytes.len() / 2].to_vec().into())?;
+        let events = zip_reader.drain_events();
+        assert!(matches!(events[0], ZipEvent::EntryStart(_)));
+        assert!(events[1..]
+            .iter()
+            .all(|event| matches!(event, ZipEvent::Data(_))));
+        assert!(events.len() > 1);
+
+        // The events before an error are kept, and the error follows them.
+        let mut corrupted = bytes.clone();
+        corrupted[bytes.len() / 2] ^= 0xff;
+        let mut zip_reader = ZipReader::streaming();
+        zip_reader.update(corrupted.into())?;
+        assert!(matches!(
+            zip_reader.take_event(),
+            Some(ZipEvent::EntryStart(_))
+        ));
+        assert!(zip_reader.finish().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_events() -> anyhow::Result<()> {
+        let bytes = archive_of(
+            &[("first.txt", b"first"), ("second.txt", b"second")],
+            zip::CompressionMethod::Deflated,
+        )?;
+
+        let mut zip_reader = ZipReader::streaming();
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let events = zip_reader.drain_events();
+
+        assert_eq!(events.len(), 9);
+        for (i, contents) in ["first", "second"].into_iter().enumerate() {
+            let events = &events[i * 3..];
+            assert!(
+                matches!(&events[0], ZipEvent::EntryStart(header) if header.filename.starts_with(contents))
+            );
+            assert!(matches!(&events[1], ZipEvent::Data(data) if data == contents));
+            assert!(matches!(&events[2], ZipEvent::EntryEnd(descriptor)
+                if descriptor.uncompressed_size == contents.len() as u64
+                    && descriptor.crc32 == crc32fast::hash(contents.as_bytes())));
+        }
+        assert!(
+            matches!(&events[6], ZipEvent::CentralDirectoryRecord(record)
+            if record.relative_offset_of_local_header == 0 && record.filename == "first.txt")
+        );
+        assert!(matches!(&events[7], ZipEvent::CentralDirectoryRecord(_)));
+        assert!(matches!(&events[8], ZipEvent::EndOfArchive(end) if end.number_of_entries == 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip() -> anyhow::Result<()> {
+        let bytes = archive_of(
+            &[("skipped.txt", b"skipped"), ("kept.txt", b"kept")],
+            zip::CompressionMethod::Deflated,
+        )?;
+
+        let mut zip_reader = ZipReader::streaming();
+        let mut events = Vec::new();
+        for chunk in bytes.chunks(40) {
+            zip_reader.update(bytes::Bytes::copy_from_slice(chunk))?;
+            while let Some(event) = zip_reader.take_event() {
+                if matches!(&event, ZipEvent::EntryStart(header) if header.filename == "skipped.txt")
+                {
+                    zip_reader.skip_entry();
+                }
+                events.push(event);
+            }
+        }
+        zip_reader.finish()?;
+        events.extend(zip_reader.drain_events());
+        assert!(matches!(&events[0], ZipEvent::EntryStart(_)));
+        assert!(
+            matches!(&events[1], ZipEvent::EntryStart(header) if header.filename == "kept.txt")
+        );
+        assert!(matches!(&events[2], ZipEvent::Data(data) if data == "kept"));
+        assert!(matches!(&events[3], ZipEvent::EntryEnd(_)));
+
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes::Bytes::copy_from_slice(&bytes[..45]))?;
+        assert_eq!(zip_reader.current_header().unwrap().filename, "skipped.txt");
+        zip_reader.skip_entry();
+        zip_reader.update(bytes::Bytes::copy_from_slice(&bytes[45..]))?;
+        zip_reader.finish()?;
+        let entries = zip_reader.drain_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].header().filename, "kept.txt");
+
+        let mut archive = ZipStreamReader::with_capacity(16, &bytes[..]);
+        archive.next_entry()?.unwrap().skip();
+        let mut entry = archive.next_entry()?.unwrap();
+        let mut data = String::new();
+        entry.read_to_string(&mut data)?;
+        assert_eq!(data, "kept");
+        assert!(archive.next_entry()?.is_none());
+
+        // Flushed events count as taken, so the entry can still be skipped.
+        let mut zip_reader = ZipReader::streaming();
+        zip_reader.update(bytes::Bytes::copy_from_slice(&bytes[..45]))?;
+        zip_reader.flush();
+        zip_reader.skip_entry();
+        zip_reader.update(bytes::Bytes::copy_from_slice(&bytes[45..]))?;
+        let events = zip_reader.drain_events();
+        assert!(
+            matches!(&events[0], ZipEvent::EntryStart(header) if header.filename == "kept.txt")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip");
+        let entries = ZipIterator::<_, 64>::new(File::open(&path)?)
+            .with_filter(|header| header.filename.ends_with(".png"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].header().filename, "gophercolor16x16.png");
+
+        // Entries that are filtered out are never decompressed.
+        let bytes = single_entry_zip("bzip2.txt", b"not deflated", zip::CompressionMethod::Bzip2)?;
+        let mut zip_reader =
+            ZipReader::streaming().with_filter(|header| header.uncompressed_size > 1024);
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let events = zip_reader.drain_events();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], ZipEvent::CentralDirectoryRecord(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dos_timestamps() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/time-go.zip");
+        let entry = ZipIterator::<_, 64>::new(File::open(path)?)
+            .next()
+            .unwrap()?;
+        let expected = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1509484316);
+        assert_eq!(entry.last_modified().to_system_time(), Some(expected));
+
+        // All fields zero, as written by some tools, is not a valid date.
+        assert!(DosDateTime::new(0, 0).to_system_time().is_none());
+        // February 29th only exists in leap years.
+        assert!(DosDateTime::new((20 << 9) | (2 << 5) | 29, 0).is_valid());
+        assert!(!DosDateTime::new((21 << 9) | (2 << 5) | 29, 0).is_valid());
+        // 24:00 is out of range.
+        assert!(!DosDateTime::new((20 << 9) | (1 << 5) | 1, 24 << 11).is_valid());
+
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            entry.last_modified().to_chrono().unwrap().to_string(),
+            "2017-10-31 21:11:56"
+        );
+        #[cfg(feature = "time")]
+        assert_eq!(
+            entry.last_modified().to_time().unwrap().to_string(),
+            "2017-10-31 21:11:56.0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_timestamps() -> anyhow::Result<()> {
+        let read_entry = |name: &str| -> anyhow::Result<ZipEntry> {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(name);
+            Ok(ZipIterator::<_, 64>::new(File::open(path)?)
+                .next()
+                .unwrap()?)
+        };
+        let seconds = |seconds| std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+
+        // Extended timestamp, in seconds.
+        let entry = read_entry("time-infozip.zip")?;
+        assert!(entry.header().extended_timestamp().is_some());
+        assert_eq!(entry.modified(), Some(seconds(1509509517)));
+
+        // NTFS timestamps, in 100 ns intervals, as 7-Zip writes them in the central
+        // directory.
+        let mut ntfs = vec![0x0a, 0x00, 32, 0, 0, 0, 0, 0, 1, 0, 24, 0];
+        for ticks in [
+            0x01d352c78ed93fb3u64,
+            0x01d352c7bff347fe,
+            0x01d352c78ed93fb3,
+        ] {
+            ntfs.extend(ticks.to_le_bytes());
+        }
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(local_file("ntfs.txt", 0, 0, (0, 0, 0), &ntfs, b"").into())?;
+        let entry = zip_reader.take_entry().unwrap();
+        let expected = seconds(1509509517) + std::time::Duration::from_nanos(244_817_900);
+        assert_eq!(entry.modified(), Some(expected));
+        assert!(entry.accessed().is_some());
+        assert!(entry.created().is_some());
+
+        // 7-Zip and WinZip only write the NTFS field in the central directory, which
+        // gives the time of the entries still held at finish.
+        let nanos = |secs, nanos| seconds(secs) + std::time::Duration::from_nanos(nanos);
+        for (name, modified) in [
+            ("time-7zip.zip", nanos(1509509517, 244_817_900)),
+            ("time-winzip.zip", nanos(1509509517, 244_000_000)),
+        ] {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(name);
+            let mut zip_reader = ZipReader::default();
+            zip_reader.update(std::fs::read(path)?.into())?;
+            assert_eq!(
+                zip_reader.entries()[0].modified(),
+                Some(seconds(1509484318))
+            );
+            zip_reader.finish()?;
+            let entry = zip_reader.take_entry().unwrap();
+            assert_eq!(entry.modified(), Some(modified));
+            assert_eq!(entry.created(), Some(modified));
+            assert!(entry.accessed().is_some());
+            assert_eq!(zip_reader.central_directory()[0].modified(), Some(modified));
+        }
+
+        // Entries taken before finish can be reconciled by hand.
+        let mut entry = read_entry("time-7zip.zip")?;
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/time-7zip.zip");
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(std::fs::read(path)?.into())?;
+        zip_reader.finish()?;
+        assert!(entry.reconcile_times(&zip_reader.central_directory()[0]));
+        let entry = entry.inflate()?;
+        assert_eq!(entry.modified(), Some(nanos(1509509517, 244_817_900)));
+
+        // Without either, the DOS fields are used.
+        let entry = read_entry("time-win7.zip")?;
+        assert_eq!(entry.modified(), Some(seconds(1509484318)));
+        assert_eq!(entry.accessed(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_fields() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/time-infozip.zip");
+        let entry = ZipIterator::<_, 64>::new(File::open(path)?)
+            .next()
+            .unwrap()?;
+        let fields = entry
+            .header()
+            .extra_fields()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::from)?;
+        assert_eq!(
+            fields,
+            [
+                ExtraField::ExtendedTimestamp(ExtendedTimestamp {
+                    mtime: Some(1509509517),
+                    atime: Some(1509509517),
+                    ctime: None,
+                }),
+                ExtraField::UnixOwner(UnixOwner {
+                    uid: 1000,
+                    gid: 1000
+                }),
+            ]
+        );
+
+        let mut extra = Vec::new();
+        extra.extend(b"\x75\x70\x0a\x00\x01");
+        extra.extend(crc32fast::hash(b"caf\x82").to_le_bytes());
+        extra.extend("café".as_bytes());
+        extra.extend(b"\x01\x99\x07\x00\x02\x00AE\x03\x08\x00");
+        extra.extend(b"\x34\x12\x02\x00\xff\xff");
+        extra.extend(b"\x55\x54\x00\x00");
+        extra.extend(b"\x0a\x00\x08\x00\x00\x00");
+        let fields = ExtraFields::new(&extra).collect::<Vec<_>>();
+        assert!(matches!(&fields[0], Ok(ExtraField::UnicodePath(path))
+            if path.text == "café" && path.matches(b"caf\x82")));
+        assert!(matches!(&fields[1], Ok(ExtraField::Aes(aes))
+            if aes.strength == 3 && aes.compression_method == 8));
+        assert!(
+            matches!(&fields[2], Ok(ExtraField::Unknown { id: 0x1234, data }) if data == b"\xff\xff")
+        );
+        // An empty extended timestamp can not be decoded, but the following
+        // fields can still be read up to the one running past the end.
+        assert!(matches!(&fields[3], Err(FormatError::InvalidExtraField)));
+        assert!(matches!(&fields[4], Err(FormatError::InvalidExtraField)));
+        assert_eq!(fields.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filename_encoding() -> anyhow::Result<()> {
+        let read_entry = |zip: ZipIterator<File, 64>| -> anyhow::Result<ZipEntry> {
+            Ok(zip.into_iter().next().unwrap()?)
+        };
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/cp-437.zip");
+        let entry = read_entry(ZipIterator::new(File::open(&path)?))?;
+        assert!(!entry.header().is_utf8());
+        assert_eq!(entry.name(), "français");
+        assert_eq!(entry.header().file_name_raw, b"fran\x87ais");
+
+        // A legacy decoder is used instead of CP437, here for names in UTF-8 but
+        // not flagged as such.
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/utf8-osx.zip");
+        let entry = read_entry(ZipIterator::new(File::open(&path)?))?;
+        assert_ne!(entry.name(), "世界");
+        let zip = ZipIterator::new(File::open(&path)?)
+            .with_legacy_encoding(|raw| String::from_utf8(raw.to_vec()).ok());
+        assert_eq!(read_entry(zip)?.name(), "世界");
+
+        // The Unicode path extra field is preferred while it matches the name.
+        let mut extra = b"\x75\x70\x0a\x00\x01".to_vec();
+        extra.extend(crc32fast::hash(b"caf\x82").to_le_bytes());
+        extra.extend("café".as_bytes());
+        let mut bytes = local_file("caf?", 0, 0, (0, 0, 0), &extra, b"");
+        bytes[33] = 0x82;
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.clone().into())?;
+        assert_eq!(zip_reader.take_entry().unwrap().name(), "café");
+        bytes[30] = b'C';
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        assert_eq!(zip_reader.take_entry().unwrap().name(), "Café");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validation() -> anyhow::Result<()> {
+        let bytes = archive_of(
+            &[("first.txt", b"first"), ("second.txt", b"second")],
+            zip::CompressionMethod::Deflated,
+        )?;
+        let validate = |bytes: &[u8]| {
+            let mut zip_reader = ZipReader::streaming().with_validation();
+            zip_reader.update(bytes.to_vec().into())?;
+            zip_reader.finish()
+        };
+        validate(&bytes)?;
+
+        let central_directory = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .unwrap();
+
+        // Different contents for the first entry in the central directory.
+        let mut tampered = bytes.clone();
+        tampered[central_directory + 16] ^= 0xff;
+        assert!(matches!(
+            validate(&tampered),
+            Err(Error::Format(FormatError::CentralRecordMismatch { name, offset: 0 }))
+                if name == "first.txt"
+        ));
+
+        // A different name for the first entry in its local header.
+        let mut tampered = bytes.clone();
+        tampered[30] = b'F';
+        assert!(matches!(
+            validate(&tampered),
+            Err(Error::Format(FormatError::CentralRecordMismatch { name, .. }))
+                if name == "First.txt"
+        ));
+
+        // The first record points elsewhere.
+        let mut tampered = bytes.clone();
+        tampered[central_directory + 42] = 1;
+        assert!(matches!(
+            validate(&tampered),
+            Err(Error::Format(FormatError::MissingCentralRecord {
+                offset: 0,
+                ..
+            }))
+        ));
+
+        // The archive ends right after the central directory.
+        let end = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x05\x06")
+            .unwrap();
+        assert!(matches!(
+            validate(&bytes[..end]),
+            Err(Error::Format(FormatError::DirectoryEndSignatureNotFound))
+        ));
+
+        // The end record locates the central directory elsewhere.
+        let mut tampered = bytes.clone();
+        tampered[end + 16] ^= 1;
+        assert!(matches!(
+            validate(&tampered),
+            Err(Error::Format(FormatError::DirectoryPositionMismatch { actual, .. }))
+                if actual == central_directory as u64
+        ));
+
+        // Another archive follows the first one, which readers going through the
+        // central directory from the end would read instead.
+        let benign = archive_of(&[("report.txt", b"benign")], zip::CompressionMethod::Stored)?;
+        let malicious = archive_of(
+            &[("report.txt", b"MALICIOUS")],
+            zip::CompressionMethod::Stored,
+        )?;
+        let concatenated = [benign.as_slice(), &malicious].concat();
+        assert!(matches!(
+            validate(&concatenated),
+            Err(Error::Format(FormatError::TrailingData { offset }))
+                if offset == benign.len() as u64
+        ));
+
+        // Without validation, none of this is checked.
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(concatenated.into())?;
+        zip_reader.finish()?;
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(tampered.into())?;
+        zip_reader.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip64_end_of_central_directory() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/zip64.zip");
+        let bytes = std::fs::read(path)?;
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.clone().into())?;
+        zip_reader.finish()?;
+
+        let record_offset = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x06\x06")
+            .unwrap();
+        let end = zip_reader.zip64_end_of_central_directory().unwrap();
+        assert_eq!(end.number_of_entries, 1);
+        let locator = zip_reader.zip64_end_of_central_directory_locator().unwrap();
+        assert_eq!(
+            locator.offset_of_zip64_end_of_central_directory,
+            record_offset as u64
+        );
+        assert_eq!(zip_reader.number_of_entries(), Some(1));
+
+        // When validating, the locator has to point to the record.
+        let validate = |bytes: &[u8]| {
+            let mut zip_reader = ZipReader::default().with_validation();
+            zip_reader.update(bytes.to_vec().into())?;
+            zip_reader.finish()
+        };
+        validate(&bytes)?;
+        let locator_offset = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x06\x07")
+            .unwrap();
+        let mut misplaced = bytes.clone();
+        misplaced[locator_offset + 8] ^= 1;
+        assert!(matches!(
+            validate(&misplaced),
+            Err(Error::Format(FormatError::DirectoryPositionMismatch { actual, .. }))
+                if actual == record_offset as u64
+        ));
+
+        // Its number of entries also has to match the central directory.
+        let mut miscounted = bytes.clone();
+        miscounted[record_offset + 32] = 2;
+        assert!(matches!(
+            validate(&miscounted),
+            Err(Error::Format(FormatError::InvalidCentralRecord {
+                expected: 2,
+                actual: 1
+            }))
+        ));
+
+        // The record can neither be smaller than its fixed fields, nor longer than
+        // the rest of the archive.
+        for record_size in [43u64, 1 << 40] {
+            let mut malformed = bytes.clone();
+            malformed[record_offset + 4..record_offset + 12]
+                .copy_from_slice(&record_size.to_le_bytes());
+            let mut zip_reader = ZipReader::default();
+            zip_reader.update(malformed.into())?;
+            // The entry before the record is still returned.
+            assert!(zip_reader.take_entry().is_some());
+            assert!(matches!(
+                zip_reader.finish(),
+                Err(Error::Format(FormatError::Directory64EndRecordInvalid))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_comments() -> anyhow::Result<()> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/test.zip");
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(std::fs::read(path)?.into())?;
+        zip_reader.finish()?;
+        assert_eq!(zip_reader.comment(), Some("This is a zipfile comment."));
+
+        // An entry with a UTF-8 file comment, in an archive with a CP437 comment.
+        let mut bytes = local_file("a.txt", 0x0800, 0, (0, 0, 0), b"", b"");
+        bytes.extend(b"PK\x01\x02\x14\x00\x14\x00\x00\x08");
+        bytes.extend([0; 18]);
+        bytes.extend(5u16.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        bytes.extend(("résumé".len() as u16).to_le_bytes());
+        bytes.extend([0; 12]);
+        bytes.extend(b"a.txt");
+        bytes.extend("résumé".as_bytes());
+        bytes.extend(b"PK\x05\x06");
+        bytes.extend([0, 0, 0, 0, 1, 0, 1, 0]);
+        bytes.extend([0; 8]);
+        bytes.extend(6u16.to_le_bytes());
+        bytes.extend(b"r\x82sum\x82");
+
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(bytes.into())?;
+        zip_reader.finish()?;
+        let record = &zip_reader.central_directory()[0];
+        assert_eq!(record.file_comment, "résumé");
+        assert_eq!(zip_reader.comment(), Some("résumé"));
+        assert_eq!(
+            zip_reader
+                .end_of_central_directory()
+                .unwrap()
+                .zip_file_comment_raw,
+            b"r\x82sum\x82"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_attributes() -> anyhow::Result<()> {
+        let read_central_directory = |name: &str| -> anyhow::Result<Vec<CentralDirectoryHeader>> {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(name);
+            let mut zip_reader = ZipReader::default();
+            zip_reader.update(std::fs::read(path)?.into())?;
+            zip_reader.finish()?;
+            Ok(zip_reader.central_directory().to_vec())
+        };
+
+        let records = read_central_directory("unix.zip")?;
+        let summary = records
+            .iter()
+            .map(|record| {
+                (
+                    record.filename.as_str(),
+                    record.kind(),
+                    record.unix_permissions(),
+                    record.is_read_only(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("hello", EntryKind::File, Some(0o666), false),
+                ("dir/bar", EntryKind::File, Some(0o666), false),
+                ("dir/empty/", EntryKind::Directory, Some(0o777), false),
+                ("readonly", EntryKind::File, Some(0o444), true),
+            ]
+        );
+
+        let records = read_central_directory("symlink.zip")?;
+        assert!(records[0].is_symlink());
+        assert_eq!(records[0].unix_mode(), Some(0o120777));
+
+        // Without a Unix mode, only the MS-DOS attributes are available.
+        let records = read_central_directory("cp-437.zip")?;
+        assert_eq!(records[0].host_system(), 0);
+        assert_eq!(records[0].unix_mode(), None);
+        assert_eq!(records[0].dos_attributes(), DOS_ARCHIVE);
+        assert!(records[0].is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_kind() -> anyhow::Result<()> {
+        let testdata = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata");
+
+        // The local headers tell directories apart by their name.
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(std::fs::read(testdata.join("unix.zip"))?.into())?;
+        let kinds = zip_reader
+            .drain_entries()
+            .iter()
+            .map(|entry| (entry.name().to_owned(), entry.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("hello".to_owned(), EntryKind::File),
+                ("dir/bar".to_owned(), EntryKind::File),
+                ("dir/empty/".to_owned(), EntryKind::Directory),
+                ("readonly".to_owned(), EntryKind::File),
+            ]
+        );
+
+        // Symbolic links are only known from the central directory, which is
+        // reconciled with the entries still held at finish.
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(std::fs::read(testdata.join("symlink.zip"))?.into())?;
+        zip_reader.finish()?;
+        let entry = zip_reader.drain_entries().remove(0);
+        assert!(entry.is_symlink());
+        let entry = entry.inflate()?;
+        assert!(entry.is_symlink());
+        assert_eq!(entry.offset(), 0);
+
+        // Entries taken before finish can be reconciled by hand.
+        let mut zip_reader = ZipReader::default();
+        zip_reader.update(std::fs::read(testdata.join("symlink.zip"))?.into())?;
+        let mut entry = zip_reader.drain_entries().remove(0);
+        assert_eq!(entry.kind(), EntryKind::File);
+        zip_reader.finish()?;
+        let records = zip_reader.central_directory();
+        assert!(entry.reconcile_kind(&records[0]));
+        assert!(entry.is_symlink());
+        Ok(())
+    }
+
+    /// Returns an empty directory for a test to extract into.
+    fn scratch_dir(name: &str) -> anyhow::Result<PathBuf> {
+        let dir =
+            std::env::temp_dir().join(format!("stream-unzip-{}-{}", name, std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_extract_to() -> anyhow::Result<()> {
+        let scratch = scratch_dir("extract")?;
+        let target = scratch.join("out");
+        let bytes = archive_of(
+            &[
+                ("a/b/c.txt", b"nested"),
+                ("a/empty/", b""),
+                ("../evil.txt", b"evil"),
+                ("a/../../evil.txt", b"evil"),
+                ("/abs.txt", b"evil"),
+                ("C:/drive.txt", b"evil"),
+                ("\\server\\share.txt", b"evil"),
+            ],
+            zip::CompressionMethod::Deflated,
+        )?;
+
+        let mut archive = ZipStreamReader::new(bytes.as_slice());
+        let report = archive.extract_to(&target)?;
+        let outcomes = report
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.outcome))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                ("a/b/c.txt", ExtractOutcome::File { size: 6 }),
+                ("a/empty/", ExtractOutcome::Directory),
+                ("../evil.txt", ExtractOutcome::UnsafePath),
+                ("a/../../evil.txt", ExtractOutcome::UnsafePath),
+                ("/abs.txt", ExtractOutcome::UnsafePath),
+                ("C:/drive.txt", ExtractOutcome::UnsafePath),
+                ("\\server\\share.txt", ExtractOutcome::UnsafePath),
+            ]
+        );
+        assert_eq!(std::fs::read(target.join("a/b/c.txt"))?, b"nested");
+        assert!(target.join("a/empty").is_dir());
+        assert!(!scratch.join("evil.txt").exists());
+
+        // An existing link out of the target is not followed.
+        #[cfg(unix)]
+        {
+            let outside = scratch.join("outside");
+            std::fs::create_dir(&outside)?;
+            std::os::unix::fs::symlink(&outside, target.join("link"))?;
+            std::os::unix::fs::symlink(outside.join("file.txt"), target.join("dangling"))?;
+            let bytes = archive_of(
+                &[("link/file.txt", b"evil"), ("dangling", b"evil")],
+                zip::CompressionMethod::Deflated,
+            )?;
+            let report = ZipStreamReader::new(bytes.as_slice()).extract_to(&target)?;
+            assert!(report
+                .iter()
+                .all(|entry| entry.outcome == ExtractOutcome::SymlinkEscape));
+            assert!(!outside.join("file.txt").exists());
+        }
+
+        // Entries that can not be written are reported, and the others still extracted.
+        let bytes = archive_of(
+            &[
+                ("f", b"file"),
+                ("f/g", b"blocked"),
+                ("f/", b""),
+                ("after.txt", b"after"),
+            ],
+            zip::CompressionMethod::Deflated,
+        )?;
+        let report = ZipStreamReader::new(bytes.as_slice()).extract_to(&target)?;
+        let outcomes = report.iter().map(|entry| entry.outcome).collect::<Vec<_>>();
+        let blocked = ExtractOutcome::Failed(std::io::ErrorKind::NotADirectory);
+        assert_eq!(
+            outcomes,
+            [
+                ExtractOutcome::File { size: 4 },
+                blocked,
+                blocked,
+                ExtractOutcome::File { size: 5 },
+            ]
+        );
+
+        // An entry that can not be read is removed, and ends the extraction.
+        let mut bytes = archive_of(
+            &[
+                ("good.txt", b"good"),
+                ("bad.txt", b"bad data"),
+                ("after.txt", b"after"),
+            ],
+            zip::CompressionMethod::Stored,
+        )?;
+        let data = bytes
+            .windows(8)
+            .position(|window| window == b"bad data")
+            .unwrap();
+        bytes[data] ^= 0xff;
+        let report = ZipStreamReader::new(bytes.as_slice()).extract_to(&target)?;
+        let outcomes = report.iter().map(|entry| entry.outcome).collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                ExtractOutcome::File { size: 4 },
+                ExtractOutcome::ReadFailed(std::io::ErrorKind::Other),
+            ]
+        );
+        assert!(target.join("good.txt").exists());
+        assert!(!target.join("bad.txt").exists());
+
+        std::fs::remove_dir_all(&scratch)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_extract_to() -> anyhow::Result<()> {
+        let scratch = scratch_dir("async-extract")?;
+        let file = tokio::fs::File::open(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/unix.zip"),
+        )
+        .await?;
+        let report = AsyncZipStreamReader::new(file).extract_to(&scratch).await?;
+        let outcomes = report
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.outcome))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                ("hello", ExtractOutcome::File { size: 8 }),
+                ("dir/bar", ExtractOutcome::File { size: 6 }),
+                ("dir/empty/", ExtractOutcome::Directory),
+                ("readonly", ExtractOutcome::File { size: 12 }),
+            ]
+        );
+        assert!(scratch.join("dir/empty").is_dir());
+        assert_eq!(std::fs::read(scratch.join("dir/bar"))?.len(), 6);
+
+        let bytes = archive_of(
+            &[("hello/x", b"blocked"), ("after.txt", b"after")],
+            zip::CompressionMethod::Deflated,
+        )?;
+        let report = AsyncZipStreamReader::new(bytes.as_slice())
+            .extract_to(&scratch)
+            .await?;
+        assert_eq!(
+            report[0].outcome,
+            ExtractOutcome::Failed(std::io::ErrorKind::NotADirectory)
+        );
+        assert_eq!(report[1].outcome, ExtractOutcome::File { size: 5 });
+
+        // Names out of the target are left out.
+        let bytes = archive_of(
+            &[
+                ("../evil.txt", b"evil"),
+                ("dir/../../evil.txt", b"evil"),
+                ("/abs.txt", b"evil"),
+                ("C:/drive.txt", b"evil"),
+                ("\\server\\share.txt", b"evil"),
+            ],
+            zip::CompressionMethod::Deflated,
+        )?;
+        let target = scratch.join("out");
+        let report = AsyncZipStreamReader::new(bytes.as_slice())
+            .extract_to(&target)
+            .await?;
+        assert_eq!(report.len(), 5);
+        assert!(report
+            .iter()
+            .all(|entry| entry.outcome == ExtractOutcome::UnsafePath && entry.path.is_none()));
+        assert!(!scratch.join("evil.txt").exists());
+
+        // So are the paths through a link out of the target.
+        #[cfg(unix)]
+        {
+            let outside = scratch.join("outside");
+            std::fs::create_dir(&outside)?;
+            std::os::unix::fs::symlink(&outside, target.join("link"))?;
+            std::os::unix::fs::symlink(outside.join("file.txt"), target.join("dangling"))?;
+            let bytes = archive_of(
+                &[("link/file.txt", b"evil"), ("dangling", b"evil")],
+                zip::CompressionMethod::Deflated,
+            )?;
+            let report = AsyncZipStreamReader::new(bytes.as_slice())
+                .extract_to(&target)
+                .await?;
+            assert_eq!(report.len(), 2);
+            assert!(report
+                .iter()
+                .all(|entry| entry.outcome == ExtractOutcome::SymlinkEscape));
+            assert!(!outside.join("file.txt").exists());
+        }
+
+        // An entry that can not be read is removed.
+        let mut bytes = archive_of(&[("bad.txt", b"bad data")], zip::CompressionMethod::Stored)?;
+        let data = bytes
+            .windows(8)
+            .position(|window| window == b"bad data")
+            .unwrap();
+        bytes[data] ^= 0xff;
+        let report = AsyncZipStreamReader::new(bytes.as_slice())
+            .extract_to(&target)
+            .await?;
+        assert_eq!(
+            report[0].outcome,
+            ExtractOutcome::ReadFailed(std::io::ErrorKind::Other)
+        );
+        assert!(!target.join("bad.txt").exists());
+
+        std::fs::remove_dir_all(&scratch)?;
+        Ok(())
     }
 }
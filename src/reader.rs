@@ -1,23 +1,64 @@
-use std::collections::VecDeque;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::SystemTime,
+};
 
 use bytes::{Buf, Bytes, BytesMut};
 
+use crate::{
+    attributes::{entry_kind, unix_mode, EntryKind, DOS_HIDDEN, DOS_READ_ONLY},
+    datetime::DosDateTime,
+    decoder::Decoder,
+    encoding::{NameDecoder, FLAG_UTF8},
+    extra::{
+        zip64_field, ExtendedTimestamp, ExtraField, ExtraFields, NtfsTimestamps,
+        Zip64ExtendedInformation, Zip64Fields, ZIP64_SIZE,
+    },
+    FormatError,
+};
+
 static H_LOCAL_FILE: [u8; 4] = [b'P', b'K', 0x03, 0x04];
 static H_DATA_DESCRIPTOR: [u8; 4] = [b'P', b'K', 0x07, 0x08];
 static H_CENTRAL_DIRECTORY: [u8; 4] = [b'P', b'K', 0x01, 0x02];
 static H_EO_CENTRAL_DIRECTORY: [u8; 4] = [b'P', b'K', 0x05, 0x06];
+static H_ZIP64_EO_CENTRAL_DIRECTORY: [u8; 4] = [b'P', b'K', 0x06, 0x06];
+static H_ZIP64_EO_CENTRAL_DIRECTORY_LOCATOR: [u8; 4] = [b'P', b'K', 0x06, 0x07];
+
+/// General purpose flag: the CRC-32 and sizes are in a data descriptor following the data.
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+
+/// Upper bound for the buffer reserved up front for an entry, as the header sizes
+/// can not be trusted.
+const MAX_PREALLOCATED: u64 = 1024 * 1024;
 
-// Unused but will be needed
-// static COMPRESSION_NONE: [u8; 2] = [0x00, 0x00];
-// static COMPRESSION_DEFLATE: [u8; 2] = [0x08, 0x00];
-// static ZIP64_SIZE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+/// The compression method used for an entry's data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionMethod {
+    /// The data is stored as-is (method 0).
+    Stored,
+    /// The data is compressed with deflate (method 8).
+    Deflated,
+    /// Any other method. These are recognized but can not be decompressed.
+    Unsupported(u16),
+}
+
+impl From<u16> for CompressionMethod {
+    fn from(method: u16) -> Self {
+        match method {
+            0 => CompressionMethod::Stored,
+            8 => CompressionMethod::Deflated,
+            method => CompressionMethod::Unsupported(method),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 enum Header {
     LocalFile(LocalFileHeader),
-    DataDescriptor(DataDescriptor),
     CentralDirectory(CentralDirectoryHeader),
     EndOfCentralDirectory(EndOfCentralDirectory),
+    Zip64EndOfCentralDirectory(Zip64EndOfCentralDirectory),
+    Zip64EndOfCentralDirectoryLocator(Zip64EndOfCentralDirectoryLocator),
 }
 
 impl Header {}
@@ -30,30 +71,130 @@ pub struct LocalFileHeader {
     pub last_mod_time: u16,
     pub last_mod_date: u16,
     pub crc32: u32,
-    pub compressed_size: u32,
-    pub uncompressed_size: u32,
+    /// The compressed size, taken from the ZIP64 extra field when present.
+    pub compressed_size: u64,
+    /// The uncompressed size, taken from the ZIP64 extra field when present.
+    pub uncompressed_size: u64,
     pub file_name_length: u16,
     pub extra_field_length: u16,
+    /// The decoded filename. See [ZipReader::with_legacy_encoding].
     pub filename: String,
+    /// The filename as stored in the header.
+    pub file_name_raw: Vec<u8>,
     pub extra_field: Vec<u8>,
+    /// The decoded ZIP64 extra field, if the entry has one.
+    pub zip64: Option<Zip64ExtendedInformation>,
 }
 
 impl LocalFileHeader {
     fn size() -> usize {
         26
     }
+
+    /// Returns the compression method of the entry.
+    pub fn compression_method(&self) -> CompressionMethod {
+        self.compression.into()
+    }
+
+    /// Returns the last modification time of the entry.
+    pub fn last_modified(&self) -> DosDateTime {
+        DosDateTime::new(self.last_mod_date, self.last_mod_time)
+    }
+
+    /// Returns the last modification time of the entry as a `SystemTime`.
+    ///
+    /// It is taken from the NTFS or extended timestamp extra field when present, as
+    /// those are in UTC and more precise. Otherwise it falls back to the DOS fields,
+    /// see [DosDateTime::to_system_time]. Returns `None` when none of these are valid.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.ntfs_timestamps()
+            .and_then(|times| times.modified())
+            .or_else(|| self.extended_timestamp()?.modified())
+            .or_else(|| self.last_modified().to_system_time())
+    }
+
+    /// Returns the last access time of the entry, from the NTFS or extended timestamp
+    /// extra field.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        self.ntfs_timestamps()
+            .and_then(|times| times.accessed())
+            .or_else(|| self.extended_timestamp()?.accessed())
+    }
+
+    /// Returns the creation time of the entry, from the NTFS or extended timestamp
+    /// extra field.
+    pub fn created(&self) -> Option<SystemTime> {
+        self.ntfs_timestamps()
+            .and_then(|times| times.created())
+            .or_else(|| self.extended_timestamp()?.created())
+    }
+
+    /// Returns the decoded extended timestamp extra field, if present and valid.
+    pub fn extended_timestamp(&self) -> Option<ExtendedTimestamp> {
+        self.extra_fields().find_map(|field| match field {
+            Ok(ExtraField::ExtendedTimestamp(timestamp)) => Some(timestamp),
+            _ => None,
+        })
+    }
+
+    /// Returns the decoded timestamps of the NTFS extra field, if present and valid.
+    pub fn ntfs_timestamps(&self) -> Option<NtfsTimestamps> {
+        self.extra_fields().find_map(|field| match field {
+            Ok(ExtraField::Ntfs(timestamps)) => Some(timestamps),
+            _ => None,
+        })
+    }
+
+    /// Iterates over the decoded fields of the extra field block.
+    pub fn extra_fields(&self) -> ExtraFields<'_> {
+        ExtraFields::with_zip64_fields(
+            &self.extra_field,
+            self.zip64
+                .as_ref()
+                .map(|zip64| zip64.fields())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns what the entry holds, as far as the local header tells: entries named
+    /// with a trailing `/` are directories, and anything else is taken to be a file.
+    ///
+    /// Symbolic links are only recorded in the central directory, see
+    /// [CentralDirectoryHeader::kind].
+    pub fn kind(&self) -> EntryKind {
+        entry_kind(None, 0, &self.filename)
+    }
+
+    /// Returns true when bit 11 of the flags is set, meaning the filename is
+    /// encoded in UTF-8.
+    pub fn is_utf8(&self) -> bool {
+        self.flags & FLAG_UTF8 != 0
+    }
+
+    /// Returns true when bit 3 of the flags is set, meaning the entry data is
+    /// followed by a data descriptor.
+    pub fn has_data_descriptor(&self) -> bool {
+        self.flags & FLAG_DATA_DESCRIPTOR != 0
+    }
 }
 
+/// The data descriptor that follows the data of an entry when bit 3 of its flags is set.
+///
+/// Entries with a ZIP64 extra field use the ZIP64 form, where both sizes are 8 bytes.
 #[derive(Debug, Clone)]
 pub struct DataDescriptor {
     pub crc32: u32,
-    pub compressed_size: u32,
-    pub uncompressed_size: u32,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
 }
 
 impl DataDescriptor {
-    fn size() -> usize {
-        12
+    fn size(zip64: bool) -> usize {
+        if zip64 {
+            20
+        } else {
+            12
+        }
     }
 }
 
@@ -66,21 +207,175 @@ pub struct CentralDirectoryHeader {
     pub last_mod_time: u16,
     pub last_mod_date: u16,
     pub crc32: u32,
-    pub compressed_size: u32,
-    pub uncompressed_size: u32,
+    /// The compressed size, taken from the ZIP64 extra field when present.
+    pub compressed_size: u64,
+    /// The uncompressed size, taken from the ZIP64 extra field when present.
+    pub uncompressed_size: u64,
     pub file_name_length: u16,
     pub extra_field_length: u16,
     pub file_comment_length: u16,
     pub disk_number_start: u16,
     pub internal_file_attributes: u16,
     pub external_file_attributes: u32,
-    pub relative_offset_of_local_header: u32,
+    /// The offset of the local header, taken from the ZIP64 extra field when present.
+    pub relative_offset_of_local_header: u64,
+    /// The decoded filename, as for [LocalFileHeader::filename].
+    pub filename: String,
+    /// The filename as stored in the record.
+    pub file_name_raw: Vec<u8>,
+    pub extra_field: Vec<u8>,
+    /// The decoded file comment, like the filename.
+    pub file_comment: String,
+    /// The file comment as stored in the record.
+    pub file_comment_raw: Vec<u8>,
+    /// The decoded ZIP64 extra field, if the record has one.
+    pub zip64: Option<Zip64ExtendedInformation>,
 }
+
 impl CentralDirectoryHeader {
     fn size() -> usize {
         42
     }
+
+    /// Returns the compression method of the entry.
+    pub fn compression_method(&self) -> CompressionMethod {
+        self.compression.into()
+    }
+
+    /// Returns the last modification time of the entry.
+    pub fn last_modified(&self) -> DosDateTime {
+        DosDateTime::new(self.last_mod_date, self.last_mod_time)
+    }
+
+    /// Returns the last modification time of the entry as a `SystemTime`. See
+    /// [LocalFileHeader::modified].
+    ///
+    /// Some writers, such as 7-Zip and WinZip, only store the NTFS extra field in the
+    /// central directory.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.extra_times()
+            .modified
+            .or_else(|| self.last_modified().to_system_time())
+    }
+
+    /// Returns the last access time of the entry, from the NTFS or extended timestamp
+    /// extra field.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        self.extra_times().accessed
+    }
+
+    /// Returns the creation time of the entry, from the NTFS or extended timestamp
+    /// extra field.
+    pub fn created(&self) -> Option<SystemTime> {
+        self.extra_times().created
+    }
+
+    /// Returns the decoded extended timestamp extra field, if present and valid.
+    pub fn extended_timestamp(&self) -> Option<ExtendedTimestamp> {
+        self.extra_fields().find_map(|field| match field {
+            Ok(ExtraField::ExtendedTimestamp(timestamp)) => Some(timestamp),
+            _ => None,
+        })
+    }
+
+    /// Returns the decoded timestamps of the NTFS extra field, if present and valid.
+    pub fn ntfs_timestamps(&self) -> Option<NtfsTimestamps> {
+        self.extra_fields().find_map(|field| match field {
+            Ok(ExtraField::Ntfs(timestamps)) => Some(timestamps),
+            _ => None,
+        })
+    }
+
+    /// Returns the times recorded in the NTFS or extended timestamp extra field.
+    fn extra_times(&self) -> EntryTimes {
+        let ntfs = self.ntfs_timestamps();
+        let extended = self.extended_timestamp();
+        EntryTimes {
+            modified: ntfs
+                .as_ref()
+                .and_then(|times| times.modified())
+                .or_else(|| extended.as_ref()?.modified()),
+            accessed: ntfs
+                .as_ref()
+                .and_then(|times| times.accessed())
+                .or_else(|| extended.as_ref()?.accessed()),
+            created: ntfs
+                .as_ref()
+                .and_then(|times| times.created())
+                .or_else(|| extended.as_ref()?.created()),
+        }
+    }
+
+    /// Iterates over the decoded fields of the extra field block.
+    pub fn extra_fields(&self) -> ExtraFields<'_> {
+        ExtraFields::with_zip64_fields(
+            &self.extra_field,
+            self.zip64
+                .as_ref()
+                .map(|zip64| zip64.fields())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns the system the entry was written on, from the upper byte of
+    /// `version_made_by`: 0 for MS-DOS, 3 for Unix, 19 for OS X, among others.
+    pub fn host_system(&self) -> u8 {
+        (self.version_made_by >> 8) as u8
+    }
+
+    /// Returns the Unix mode of the entry, with its file type and permission bits,
+    /// when it was written on a Unix-like system.
+    pub fn unix_mode(&self) -> Option<u32> {
+        unix_mode(self.version_made_by, self.external_file_attributes)
+    }
+
+    /// Returns the Unix permission bits of the entry, such as `0o644`.
+    pub fn unix_permissions(&self) -> Option<u32> {
+        self.unix_mode().map(|mode| mode & 0o7777)
+    }
+
+    /// Returns the MS-DOS attribute flags of the entry, from the lower byte of the
+    /// external attributes.
+    pub fn dos_attributes(&self) -> u8 {
+        self.external_file_attributes as u8
+    }
+
+    /// Returns true when the entry is flagged as read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.dos_attributes() & DOS_READ_ONLY != 0
+    }
+
+    /// Returns true when the entry is flagged as hidden.
+    pub fn is_hidden(&self) -> bool {
+        self.dos_attributes() & DOS_HIDDEN != 0
+    }
+
+    /// Returns what the entry holds, from its Unix mode when it has one, and
+    /// otherwise from its MS-DOS directory flag or a trailing `/` in its name.
+    pub fn kind(&self) -> EntryKind {
+        entry_kind(
+            self.unix_mode(),
+            self.external_file_attributes,
+            &self.filename,
+        )
+    }
+
+    /// Returns true when the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.kind() == EntryKind::Directory
+    }
+
+    /// Returns true when the entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.kind() == EntryKind::Symlink
+    }
+
+    /// Returns true when the entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.kind() == EntryKind::File
+    }
 }
+
 #[derive(Debug, Clone)]
 pub struct EndOfCentralDirectory {
     pub disk_number: u16,
@@ -90,24 +385,98 @@ pub struct EndOfCentralDirectory {
     pub size_of_central_directory: u32,
     pub offset_of_start_of_central_directory: u32,
     pub zip_file_comment_length: u16,
+    /// The decoded archive comment. As it has no flags, it is decoded like the
+    /// filenames not flagged as UTF-8.
+    pub zip_file_comment: String,
+    /// The archive comment as stored in the record.
+    pub zip_file_comment_raw: Vec<u8>,
 }
 
 impl EndOfCentralDirectory {
+    fn size() -> usize {
+        18
+    }
+}
+
+/// The ZIP64 end of central directory record, which precedes the end of central
+/// directory record of archives with too many entries or too large for it.
+#[derive(Debug, Clone)]
+pub struct Zip64EndOfCentralDirectory {
+    /// The size of the rest of the record, following this field.
+    pub record_size: u64,
+    pub version_made_by: u16,
+    pub version_needed_to_extract: u16,
+    pub disk_number: u32,
+    pub disk_with_central_directory: u32,
+    pub number_of_entries_on_disk: u64,
+    pub number_of_entries: u64,
+    pub size_of_central_directory: u64,
+    pub offset_of_start_of_central_directory: u64,
+    pub extensible_data: Vec<u8>,
+}
+
+impl Zip64EndOfCentralDirectory {
+    fn size() -> usize {
+        52
+    }
+}
+
+/// The ZIP64 end of central directory locator, which follows the ZIP64 end of central
+/// directory record.
+#[derive(Debug, Clone)]
+pub struct Zip64EndOfCentralDirectoryLocator {
+    pub disk_with_zip64_end_of_central_directory: u32,
+    pub offset_of_zip64_end_of_central_directory: u64,
+    pub total_number_of_disks: u32,
+}
+
+impl Zip64EndOfCentralDirectoryLocator {
     fn size() -> usize {
         16
     }
 }
 
-fn decode_header(b: &mut BytesMut) -> Option<Header> {
+/// Decodes the data descriptor at the start of the buffer, with or without its signature.
+///
+/// Returns `None` when more data is needed to decode it.
+fn decode_data_descriptor(b: &mut BytesMut, zip64: bool) -> Option<DataDescriptor> {
     if b.remaining() < 4 {
         return None;
     }
+    let signed = b[0..4] == H_DATA_DESCRIPTOR;
+    let size = DataDescriptor::size(zip64) + if signed { 4 } else { 0 };
+    if b.remaining() < size {
+        return None;
+    }
+    if signed {
+        b.advance(4);
+    }
+    let crc32 = b.get_u32_le();
+    let (compressed_size, uncompressed_size) = if zip64 {
+        (b.get_u64_le(), b.get_u64_le())
+    } else {
+        (b.get_u32_le() as u64, b.get_u32_le() as u64)
+    };
+    Some(DataDescriptor {
+        crc32,
+        compressed_size,
+        uncompressed_size,
+    })
+}
+
+/// Decodes the header at the start of the buffer.
+///
+/// Returns `Ok(None)` when more data is needed to decode it.
+fn decode_header(b: &mut BytesMut, names: &NameDecoder) -> Result<Option<Header>, crate::Error> {
+    if b.remaining() < 4 {
+        return Ok(None);
+    }
     let header = &b[0..4];
 
     if header == H_LOCAL_FILE {
         let base_size = LocalFileHeader::size() + 4;
         if b.remaining() < base_size {
-            return None;
+            return Ok(None);
         }
         let mut intermediate: BytesMut = BytesMut::zeroed(base_size);
         intermediate.copy_from_slice(&b[0..base_size]);
@@ -122,14 +491,21 @@ fn decode_header(b: &mut BytesMut) -> Option<Header> {
         let uncompressed_size = intermediate.get_u32_le();
         let file_name_length = intermediate.get_u16_le();
         let extra_field_length = intermediate.get_u16_le();
-        if (b.remaining() - base_size) < (file_name_length + extra_field_length) as usize {
-            return None;
+        if (b.remaining() - base_size) < file_name_length as usize + extra_field_length as usize {
+            return Ok(None);
         } else {
             b.advance(base_size);
         }
 
-        let filename = String::from_utf8(b.split_to(file_name_length as usize).to_vec()).unwrap();
+        let file_name_raw = b.split_to(file_name_length as usize).to_vec();
         let extra_field = b.split_to(extra_field_length as usize).to_vec();
+        let filename = names
+            .decode_name(&file_name_raw, flags, &extra_field)
+            .ok_or(FormatError::InvalidLocalHeader)?;
+        let zip64 = zip64_field(
+            &extra_field,
+            Zip64Fields::local(uncompressed_size, compressed_size),
+        )?;
         let h = Header::LocalFile(LocalFileHeader {
             version,
             flags,
@@ -137,31 +513,29 @@ fn decode_header(b: &mut BytesMut) -> Option<Header> {
             last_mod_time,
             last_mod_date,
             crc32,
-            compressed_size,
-            uncompressed_size,
+            compressed_size: zip64
+                .as_ref()
+                .and_then(|zip64| zip64.compressed_size)
+                .unwrap_or(compressed_size as u64),
+            uncompressed_size: zip64
+                .as_ref()
+                .and_then(|zip64| zip64.uncompressed_size)
+                .unwrap_or(uncompressed_size as u64),
             file_name_length,
             extra_field_length,
             filename,
+            file_name_raw,
             extra_field,
+            zip64,
         });
-        Some(h)
-    } else if header == H_DATA_DESCRIPTOR {
-        if b.remaining() < DataDescriptor::size() + 4 {
-            return None;
-        }
-        b.advance(4);
-        let crc32 = b.get_u32_le();
-        let compressed_size = b.get_u32_le();
-        let uncompressed_size = b.get_u32_le();
-        let h = Header::DataDescriptor(DataDescriptor {
-            crc32,
-            compressed_size,
-            uncompressed_size,
-        });
-        Some(h)
+        Ok(Some(h))
     } else if header == H_EO_CENTRAL_DIRECTORY {
         if b.remaining() < EndOfCentralDirectory::size() + 4 {
-            return None;
+            return Ok(None);
+        }
+        let comment_length = (&b[20..22]).get_u16_le() as usize;
+        if b.remaining() < EndOfCentralDirectory::size() + 4 + comment_length {
+            return Ok(None);
         }
         b.advance(4);
         let disk_number = b.get_u16_le();
@@ -171,6 +545,8 @@ fn decode_header(b: &mut BytesMut) -> Option<Header> {
         let size_of_central_directory = b.get_u32_le();
         let offset_of_start_of_central_directory = b.get_u32_le();
         let zip_file_comment_length = b.get_u16_le();
+        let zip_file_comment_raw = b.split_to(zip_file_comment_length as usize).to_vec();
+        let zip_file_comment = names.decode_comment(&zip_file_comment_raw, 0, &[]);
         let h = Header::EndOfCentralDirectory(EndOfCentralDirectory {
             disk_number,
             disk_with_central_directory,
@@ -179,11 +555,67 @@ fn decode_header(b: &mut BytesMut) -> Option<Header> {
             size_of_central_directory,
             offset_of_start_of_central_directory,
             zip_file_comment_length,
+            zip_file_comment,
+            zip_file_comment_raw,
         });
-        Some(h)
+        Ok(Some(h))
+    } else if header == H_ZIP64_EO_CENTRAL_DIRECTORY {
+        if b.remaining() < Zip64EndOfCentralDirectory::size() + 4 {
+            return Ok(None);
+        }
+        let record_size = (&b[4..12]).get_u64_le();
+        // The fixed fields take 44 bytes, and the extensible data follows them.
+        let extensible_data_length = record_size
+            .checked_sub(44)
+            .ok_or(FormatError::Directory64EndRecordInvalid)?;
+        if ((b.remaining() - Zip64EndOfCentralDirectory::size() - 4) as u64)
+            < extensible_data_length
+        {
+            return Ok(None);
+        }
+        b.advance(12);
+        let version_made_by = b.get_u16_le();
+        let version_needed_to_extract = b.get_u16_le();
+        let disk_number = b.get_u32_le();
+        let disk_with_central_directory = b.get_u32_le();
+        let number_of_entries_on_disk = b.get_u64_le();
+        let number_of_entries = b.get_u64_le();
+        let size_of_central_directory = b.get_u64_le();
+        let offset_of_start_of_central_directory = b.get_u64_le();
+        let extensible_data = b.split_to(extensible_data_length as usize).to_vec();
+        let h = Header::Zip64EndOfCentralDirectory(Zip64EndOfCentralDirectory {
+            record_size,
+            version_made_by,
+            version_needed_to_extract,
+            disk_number,
+            disk_with_central_directory,
+            number_of_entries_on_disk,
+            number_of_entries,
+            size_of_central_directory,
+            offset_of_start_of_central_directory,
+            extensible_data,
+        });
+        Ok(Some(h))
+    } else if header == H_ZIP64_EO_CENTRAL_DIRECTORY_LOCATOR {
+        if b.remaining() < Zip64EndOfCentralDirectoryLocator::size() + 4 {
+            return Ok(None);
+        }
+        b.advance(4);
+        let h = Header::Zip64EndOfCentralDirectoryLocator(Zip64EndOfCentralDirectoryLocator {
+            disk_with_zip64_end_of_central_directory: b.get_u32_le(),
+            offset_of_zip64_end_of_central_directory: b.get_u64_le(),
+            total_number_of_disks: b.get_u32_le(),
+        });
+        Ok(Some(h))
     } else if header == H_CENTRAL_DIRECTORY {
         if b.remaining() < CentralDirectoryHeader::size() + 4 {
-            return None;
+            return Ok(None);
+        }
+        let tail_length = (&b[28..30]).get_u16_le() as usize
+            + (&b[30..32]).get_u16_le() as usize
+            + (&b[32..34]).get_u16_le() as usize;
+        if b.remaining() < CentralDirectoryHeader::size() + 4 + tail_length {
+            return Ok(None);
         }
         b.advance(4);
         let version_made_by = b.get_u16_le();
@@ -202,6 +634,22 @@ fn decode_header(b: &mut BytesMut) -> Option<Header> {
         let internal_file_attributes = b.get_u16_le();
         let external_file_attributes = b.get_u32_le();
         let relative_offset_of_local_header = b.get_u32_le();
+        let file_name_raw = b.split_to(file_name_length as usize).to_vec();
+        let extra_field = b.split_to(extra_field_length as usize).to_vec();
+        let file_comment_raw = b.split_to(file_comment_length as usize).to_vec();
+        let filename = names
+            .decode_name(&file_name_raw, flags, &extra_field)
+            .ok_or(FormatError::InvalidCentralDirectoryHeader)?;
+        let file_comment = names.decode_comment(&file_comment_raw, flags, &extra_field);
+        let zip64 = zip64_field(
+            &extra_field,
+            Zip64Fields::central(
+                uncompressed_size,
+                compressed_size,
+                relative_offset_of_local_header,
+                disk_number_start,
+            ),
+        )?;
         let h = Header::CentralDirectory(CentralDirectoryHeader {
             version_made_by,
             version_needed_to_extract,
@@ -210,19 +658,106 @@ fn decode_header(b: &mut BytesMut) -> Option<Header> {
             last_mod_time,
             last_mod_date,
             crc32,
-            compressed_size,
-            uncompressed_size,
+            compressed_size: zip64
+                .as_ref()
+                .and_then(|zip64| zip64.compressed_size)
+                .unwrap_or(compressed_size as u64),
+            uncompressed_size: zip64
+                .as_ref()
+                .and_then(|zip64| zip64.uncompressed_size)
+                .unwrap_or(uncompressed_size as u64),
             file_name_length,
             extra_field_length,
             file_comment_length,
             disk_number_start,
             internal_file_attributes,
             external_file_attributes,
-            relative_offset_of_local_header,
+            relative_offset_of_local_header: zip64
+                .as_ref()
+                .and_then(|zip64| zip64.relative_header_offset)
+                .unwrap_or(relative_offset_of_local_header as u64),
+            filename,
+            file_name_raw,
+            extra_field,
+            file_comment,
+            file_comment_raw,
+            zip64,
         });
-        Some(h)
+        Ok(Some(h))
     } else {
-        None
+        Err(crate::Error::BadHeader)
+    }
+}
+
+/// The part of the archive the reader expects next.
+#[derive(Debug, Default)]
+enum State {
+    /// A local file header or a central directory record.
+    #[default]
+    Header,
+    /// Entry data of a known compressed size.
+    Data,
+    /// Deflated entry data of unknown size, read until the deflate stream ends.
+    DeflatedData,
+    /// Stored entry data of unknown size, read until a matching data descriptor follows.
+    StoredData,
+    /// The data descriptor following the entry data.
+    DataDescriptor,
+    /// Data following the end of the archive, or unrecognized data following the
+    /// central directory, which is ignored.
+    Trailing,
+}
+
+/// An item produced by a [ZipReader] in streaming mode, in the order the
+/// corresponding records appear in the archive.
+#[derive(Debug, Clone)]
+pub enum ZipEvent {
+    /// A new entry starts. Its data follows as [ZipEvent::Data] events.
+    EntryStart(LocalFileHeader),
+    /// The next chunk of decompressed data of the current entry.
+    Data(Bytes),
+    /// The current entry ended, and its data matched the size and CRC-32 given here,
+    /// taken from the local header or the data descriptor that followed the data.
+    EntryEnd(DataDescriptor),
+    /// A record of the central directory, which follows the last entry.
+    CentralDirectoryRecord(CentralDirectoryHeader),
+    /// The ZIP64 end of central directory record, which follows the central directory
+    /// of ZIP64 archives.
+    Zip64EndOfCentralDirectory(Zip64EndOfCentralDirectory),
+    /// The ZIP64 end of central directory locator, which follows the ZIP64 end of
+    /// central directory record.
+    Zip64EndOfCentralDirectoryLocator(Zip64EndOfCentralDirectoryLocator),
+    /// The end of central directory record, which ends the archive.
+    EndOfArchive(EndOfCentralDirectory),
+}
+
+/// A predicate choosing which entries a [ZipReader] reads.
+struct EntryFilter(Box<dyn FnMut(&LocalFileHeader) -> bool + Send>);
+
+impl std::fmt::Debug for EntryFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EntryFilter")
+    }
+}
+
+/// What is checked against the central directory for each entry read.
+#[derive(Debug)]
+struct EntrySummary {
+    offset: u64,
+    name: String,
+    compression: u16,
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl EntrySummary {
+    fn matches(&self, record: &CentralDirectoryHeader) -> bool {
+        self.name == record.filename
+            && self.compression == record.compression
+            && self.crc32 == record.crc32
+            && self.compressed_size == record.compressed_size
+            && self.uncompressed_size == record.uncompressed_size
     }
 }
 
@@ -231,6 +766,45 @@ pub struct ZipReader {
     curr_entry: Option<ZipEntry>,
     buffer: BytesMut,
     entries: VecDeque<ZipEntry>,
+    state: State,
+    in_central_directory: bool,
+    /// Decompresses the current entry when streaming, or finds its end when its size is unknown.
+    decoder: Option<Decoder>,
+    /// Compressed bytes of the current entry read so far.
+    data_read: u64,
+    streaming: bool,
+    events: VecDeque<ZipEvent>,
+    /// Whether the rest of the current entry is discarded.
+    skipping: bool,
+    /// Number of `EntryStart` events pushed, and number of them taken.
+    entries_started: usize,
+    entries_taken: usize,
+    filter: Option<EntryFilter>,
+    names: NameDecoder,
+    /// The central directory records read so far, unless streaming without validation.
+    central_directory: Vec<CentralDirectoryHeader>,
+    end_of_central_directory: Option<EndOfCentralDirectory>,
+    zip64_end_of_central_directory: Option<Zip64EndOfCentralDirectory>,
+    zip64_end_of_central_directory_locator: Option<Zip64EndOfCentralDirectoryLocator>,
+    /// Number of bytes fed to the reader so far.
+    bytes_received: u64,
+    /// Offsets of the first central directory record, of the first record following
+    /// the central directory, and of the ZIP64 end of central directory record.
+    central_directory_start: Option<u64>,
+    central_directory_end: Option<u64>,
+    zip64_end_offset: Option<u64>,
+    /// Offset of the first byte following the end of central directory record.
+    trailing_data: Option<u64>,
+    /// Offset of the local header of the current entry.
+    entry_offset: u64,
+    /// The entries read so far, when validating them against the central directory.
+    validated_entries: Option<Vec<EntrySummary>>,
+    /// An error found after entries or events that had not been taken yet.
+    error: Option<crate::Error>,
+    /// Whether a header has been decoded. Data before the first one is skipped.
+    found_header: bool,
+    /// Whether the whole archive has been fed to the reader.
+    finishing: bool,
 }
 
 impl ZipReader {
@@ -238,21 +812,261 @@ impl ZipReader {
         Self::default()
     }
 
-    pub fn update(&mut self, bytes: Bytes) {
+    /// Creates a reader that decompresses entries as their data arrives instead of
+    /// collecting them.
+    ///
+    /// The decompressed data is returned as [ZipEvent]s by [ZipReader::take_event], so
+    /// memory use does not depend on the size of the entries as long as the events
+    /// are taken after each update.
+    pub fn streaming() -> Self {
+        Self {
+            streaming: true,
+            ..Self::default()
+        }
+    }
+
+    /// Only reads the entries whose local header matches `filter`.
+    ///
+    /// Other entries are skipped as their data arrives, as with
+    /// [skip_entry](Self::skip_entry), and produce neither entries nor events.
+    pub fn with_filter(
+        mut self,
+        filter: impl FnMut(&LocalFileHeader) -> bool + Send + 'static,
+    ) -> Self {
+        self.filter = Some(EntryFilter(Box::new(filter)));
+        self
+    }
+
+    /// Decodes the filenames that are not flagged as UTF-8 with `decoder`, for
+    /// archives written in a legacy encoding such as Shift-JIS.
+    ///
+    /// The decoder is only used when the entry has no Info-ZIP Unicode path extra
+    /// field. When it returns `None`, the filename is decoded as CP437, which is also
+    /// the default.
+    pub fn with_legacy_encoding(
+        mut self,
+        decoder: impl Fn(&[u8]) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.names = NameDecoder::new(Box::new(decoder));
+        self
+    }
+
+    /// Checks the entries against the central directory in [finish](Self::finish).
+    ///
+    /// Each entry read has to match the central directory record pointing to its
+    /// offset, by name, compression method, sizes and CRC-32, and each record has to
+    /// point to an entry. The end of central directory records have to point to
+    /// where the central directory was read, and the archive has to end with them.
+    /// This catches archives crafted to show different contents to
+    /// streaming readers and to readers going through the central directory.
+    ///
+    /// Offsets are counted from the first byte fed to the reader, so archives with
+    /// data prepended to them only pass validation when their offsets account for
+    /// it, as in most self-extracting archives.
+    pub fn with_validation(mut self) -> Self {
+        self.validated_entries = Some(Vec::new());
+        self
+    }
+
+    /// Feeds the next chunk of the archive to the reader.
+    ///
+    /// Returns an error when the data can not be decoded as a zip archive. When the
+    /// chunk also completes entries or events before the error, they are kept and the
+    /// error is returned by the next call to `update` or [finish](Self::finish), or
+    /// by [take_error](Self::take_error), instead, so that they can be taken first.
+    pub fn update(&mut self, bytes: Bytes) -> Result<(), crate::Error> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.bytes_received += bytes.len() as u64;
         self.buffer.extend(bytes);
-        self.process_buffer();
+        let result = self.process_buffer();
+        self.defer_error(result)
+    }
+
+    /// Keeps an error for later when there are entries or events to take before it.
+    fn defer_error(&mut self, result: Result<(), crate::Error>) -> Result<(), crate::Error> {
+        match result {
+            Err(e) if !self.entries.is_empty() || !self.events.is_empty() => {
+                self.error = Some(e);
+                Ok(())
+            }
+            result => result,
+        }
     }
 
-    pub fn finish(&mut self) {
-        self.process_buffer();
+    /// Processes any remaining data once the whole archive has been fed to the reader.
+    ///
+    /// Returns an error when the archive ends in the middle of a header or of an
+    /// entry, which is then not returned. As with [update](Self::update), the error
+    /// is kept back while there are entries or events to take before it. Also returns
+    /// an error when validation fails, right away.
+    pub fn finish(&mut self) -> Result<(), crate::Error> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.finishing = true;
+        let result = self.finish_buffer();
+        self.defer_error(result)?;
+        if self.error.is_some() {
+            return Ok(());
+        }
+        self.reconcile_entries();
+        // Validation errors concern all the entries, so they are not kept back.
+        self.validate()
+    }
+
+    fn finish_buffer(&mut self) -> Result<(), crate::Error> {
+        self.process_buffer()?;
+        // An entry whose data has not been read entirely is never returned.
         if let Some(curr_entry) = self.curr_entry.take() {
-            self.entries.push_back(curr_entry);
+            self.skipping = false;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("archive ended within entry {}", curr_entry.header.filename),
+            )
+            .into());
         }
+        // A ZIP64 end of central directory record longer than the rest of the
+        // archive would otherwise be left waiting for its data.
+        if self.buffer.starts_with(&H_ZIP64_EO_CENTRAL_DIRECTORY) {
+            return Err(FormatError::Directory64EndRecordInvalid.into());
+        }
+        if !self.in_central_directory && !self.buffer.is_empty() {
+            return Err(crate::Error::BadHeader);
+        }
+        Ok(())
+    }
+
+    /// Returns the error kept back by [update](Self::update) or
+    /// [finish](Self::finish) until the entries and events before it are taken.
+    pub fn take_error(&mut self) -> Option<crate::Error> {
+        self.error.take()
+    }
+
+    /// Takes the kind and times of the entries that have not been taken yet from the
+    /// central directory.
+    fn reconcile_entries(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let records: HashMap<_, _> = self
+            .central_directory
+            .iter()
+            .map(|record| (record.relative_offset_of_local_header, record))
+            .collect();
+        for entry in &mut self.entries {
+            if let Some(record) = records.get(&entry.offset) {
+                entry.reconcile_kind(record);
+                entry.reconcile_times(record);
+            }
+        }
+    }
+
+    /// Checks the entries read against the central directory, when validating.
+    fn validate(&self) -> Result<(), crate::Error> {
+        let entries = match &self.validated_entries {
+            Some(entries) => entries,
+            None => return Ok(()),
+        };
+        let end = self
+            .end_of_central_directory
+            .as_ref()
+            .ok_or(FormatError::DirectoryEndSignatureNotFound)?;
+        if let Some(offset) = self.trailing_data {
+            return Err(FormatError::TrailingData { offset }.into());
+        }
+        let expected = match &self.zip64_end_of_central_directory {
+            Some(end) => end.number_of_entries,
+            None => end.number_of_entries as u64,
+        };
+        let actual = self.central_directory.len() as u64;
+        if expected != actual {
+            return Err(FormatError::InvalidCentralRecord { expected, actual }.into());
+        }
+        self.validate_positions(end)?;
+
+        let mut records = HashMap::new();
+        for record in &self.central_directory {
+            let offset = record.relative_offset_of_local_header;
+            if records.insert(offset, record).is_some() {
+                return Err(FormatError::CentralRecordMismatch {
+                    name: record.filename.clone(),
+                    offset,
+                }
+                .into());
+            }
+        }
+        for entry in entries {
+            let record =
+                records
+                    .remove(&entry.offset)
+                    .ok_or_else(|| FormatError::MissingCentralRecord {
+                        name: entry.name.clone(),
+                        offset: entry.offset,
+                    })?;
+            if !entry.matches(record) {
+                return Err(FormatError::CentralRecordMismatch {
+                    name: entry.name.clone(),
+                    offset: entry.offset,
+                }
+                .into());
+            }
+        }
+        if let Some(record) = records
+            .into_values()
+            .min_by_key(|record| record.relative_offset_of_local_header)
+        {
+            return Err(FormatError::MissingLocalEntry {
+                name: record.filename.clone(),
+                offset: record.relative_offset_of_local_header,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Checks that the end of central directory records point to where the central
+    /// directory and the ZIP64 end of central directory record were read.
+    fn validate_positions(&self, end: &EndOfCentralDirectory) -> Result<(), crate::Error> {
+        let check = |expected: u64, actual: u64| {
+            if expected == actual {
+                Ok(())
+            } else {
+                Err(FormatError::DirectoryPositionMismatch { expected, actual })
+            }
+        };
+        // Values too large for the end of central directory record are only found in
+        // the ZIP64 one.
+        let check_32 = |expected: u32, actual: u64| match expected {
+            ZIP64_SIZE if self.zip64_end_of_central_directory.is_some() => Ok(()),
+            expected => check(expected as u64, actual),
+        };
+        let end_offset = self.central_directory_end.unwrap_or_default();
+        let start = self.central_directory_start.unwrap_or(end_offset);
+        let size = end_offset - start;
+        check_32(end.offset_of_start_of_central_directory, start)?;
+        check_32(end.size_of_central_directory, size)?;
+        if let Some(zip64_end) = &self.zip64_end_of_central_directory {
+            check(zip64_end.offset_of_start_of_central_directory, start)?;
+            check(zip64_end.size_of_central_directory, size)?;
+        }
+        match (
+            &self.zip64_end_of_central_directory_locator,
+            self.zip64_end_offset,
+        ) {
+            (Some(locator), Some(offset)) => {
+                check(locator.offset_of_zip64_end_of_central_directory, offset)?
+            }
+            (None, None) => {}
+            _ => return Err(FormatError::Directory64EndRecordInvalid.into()),
+        }
+        Ok(())
     }
 
     pub fn entries(&mut self) -> &[ZipEntry] {
         self.entries.make_contiguous();
-        &self.entries.as_slices().0
+        self.entries.as_slices().0
     }
 
     pub fn take_entry(&mut self) -> Option<ZipEntry> {
@@ -263,74 +1077,568 @@ impl ZipReader {
         self.entries.drain(0..).collect()
     }
 
+    /// Returns the central directory records read so far, unless streaming without
+    /// validation.
+    ///
+    /// The central directory follows the last entry, so this is only complete once
+    /// the whole archive has been read. When streaming, the records are returned as
+    /// [ZipEvent::CentralDirectoryRecord] events instead.
+    pub fn central_directory(&self) -> &[CentralDirectoryHeader] {
+        &self.central_directory
+    }
+
+    /// Returns the end of central directory record, once the archive has been read.
+    pub fn end_of_central_directory(&self) -> Option<&EndOfCentralDirectory> {
+        self.end_of_central_directory.as_ref()
+    }
+
+    /// Returns the archive comment, once the archive has been read.
+    pub fn comment(&self) -> Option<&str> {
+        self.end_of_central_directory
+            .as_ref()
+            .map(|end| end.zip_file_comment.as_str())
+    }
+
+    /// Returns the ZIP64 end of central directory record, if the archive has one.
+    pub fn zip64_end_of_central_directory(&self) -> Option<&Zip64EndOfCentralDirectory> {
+        self.zip64_end_of_central_directory.as_ref()
+    }
+
+    /// Returns the ZIP64 end of central directory locator, if the archive has one.
+    pub fn zip64_end_of_central_directory_locator(
+        &self,
+    ) -> Option<&Zip64EndOfCentralDirectoryLocator> {
+        self.zip64_end_of_central_directory_locator.as_ref()
+    }
+
+    /// Returns the number of entries in the archive according to its end of central
+    /// directory records, preferring the ZIP64 one, once the archive has been read.
+    pub fn number_of_entries(&self) -> Option<u64> {
+        self.zip64_end_of_central_directory
+            .as_ref()
+            .map(|end| end.number_of_entries)
+            .or_else(|| {
+                self.end_of_central_directory
+                    .as_ref()
+                    .map(|end| end.number_of_entries.into())
+            })
+    }
+
+    /// Returns the next event, when streaming.
+    pub fn take_event(&mut self) -> Option<ZipEvent> {
+        let event = self.events.pop_front();
+        if let Some(ZipEvent::EntryStart(_)) = event {
+            self.entries_taken += 1;
+        }
+        event
+    }
+
+    /// Returns all the pending events, when streaming.
+    pub fn drain_events(&mut self) -> Vec<ZipEvent> {
+        let events: Vec<_> = self.events.drain(0..).collect();
+        self.entries_taken += events
+            .iter()
+            .filter(|event| matches!(event, ZipEvent::EntryStart(_)))
+            .count();
+        events
+    }
+
+    /// Returns the header of the entry whose data is being read, if any.
+    pub fn current_header(&self) -> Option<&LocalFileHeader> {
+        self.curr_entry.as_ref().map(|entry| &entry.header)
+    }
+
+    /// Skips the rest of an entry, discarding its data as it arrives instead of
+    /// buffering or decompressing it.
+    ///
+    /// When streaming, this is the entry whose [ZipEvent::EntryStart] was taken last:
+    /// its pending events are dropped and no more are produced for it. Otherwise this
+    /// is the entry given by [current_header](Self::current_header), which will not
+    /// be returned. The skipped data is not checked against the size and CRC-32 of
+    /// the entry.
+    pub fn skip_entry(&mut self) {
+        if !self.streaming {
+            if let Some(curr_entry) = self.curr_entry.as_mut() {
+                curr_entry.bytes = BytesMut::new();
+                self.skipping = true;
+            }
+            return;
+        }
+        while let Some(ZipEvent::Data(_)) = self.events.front() {
+            self.events.pop_front();
+        }
+        if let Some(ZipEvent::EntryEnd(_)) = self.events.front() {
+            self.events.pop_front();
+        } else if self.entries_taken == self.entries_started && self.curr_entry.is_some() {
+            self.skipping = true;
+        }
+    }
+
     pub fn flush(&mut self) {
         self.entries.clear();
+        self.drain_events();
     }
 
-    fn process_buffer(&mut self) {
-        let mut i = 0;
-
-        while i < self.buffer.len() {
-            if let Some(header) = decode_header(&mut self.buffer) {
-                if let Some(curr_entry) = self.curr_entry.take() {
-                    self.entries.push_back(curr_entry);
+    fn process_buffer(&mut self) -> Result<(), crate::Error> {
+        loop {
+            match self.state {
+                State::Header => {
+                    let offset = self.bytes_received - self.buffer.len() as u64;
+                    let header = match decode_header(&mut self.buffer, &self.names) {
+                        Ok(Some(header)) => header,
+                        Ok(None) => return Ok(()),
+                        // Data in front of the archive, such as the stub of a
+                        // self-extracting archive, is skipped up to the first header.
+                        Err(crate::Error::BadHeader) if !self.found_header => {
+                            if !skip_to_signature(&mut self.buffer) {
+                                return Ok(());
+                            }
+                            continue;
+                        }
+                        // Unknown records following the central directory, such as a
+                        // digital signature, end processing instead of failing on them.
+                        Err(crate::Error::BadHeader) if self.in_central_directory => {
+                            self.state = State::Trailing;
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    self.found_header = true;
+                    if !matches!(header, Header::LocalFile(_) | Header::CentralDirectory(_)) {
+                        self.central_directory_end.get_or_insert(offset);
+                    }
+                    match header {
+                        Header::LocalFile(local) => {
+                            self.entry_offset = offset;
+                            self.start_entry(local)?
+                        }
+                        Header::CentralDirectory(record) => {
+                            self.in_central_directory = true;
+                            self.central_directory_start.get_or_insert(offset);
+                            if !self.streaming || self.validated_entries.is_some() {
+                                self.central_directory.push(record.clone());
+                            }
+                            if self.streaming {
+                                self.events
+                                    .push_back(ZipEvent::CentralDirectoryRecord(record));
+                            }
+                        }
+                        Header::Zip64EndOfCentralDirectory(end) => {
+                            self.in_central_directory = true;
+                            self.zip64_end_offset = Some(offset);
+                            self.zip64_end_of_central_directory = Some(end.clone());
+                            if self.streaming {
+                                self.events
+                                    .push_back(ZipEvent::Zip64EndOfCentralDirectory(end));
+                            }
+                        }
+                        Header::Zip64EndOfCentralDirectoryLocator(locator) => {
+                            self.in_central_directory = true;
+                            self.zip64_end_of_central_directory_locator = Some(locator.clone());
+                            if self.streaming {
+                                self.events
+                                    .push_back(ZipEvent::Zip64EndOfCentralDirectoryLocator(
+                                        locator,
+                                    ));
+                            }
+                        }
+                        Header::EndOfCentralDirectory(end) => {
+                            // Anything following the end of the archive is ignored.
+                            self.in_central_directory = true;
+                            self.state = State::Trailing;
+                            self.end_of_central_directory = Some(end.clone());
+                            if self.streaming {
+                                self.events.push_back(ZipEvent::EndOfArchive(end));
+                            }
+                        }
+                    }
+                }
+                State::Data => {
+                    if self.streaming && !self.skipping {
+                        if let Err(e) = self.create_decoder() {
+                            // Until its start has been taken, the entry may still be
+                            // skipped, which needs no decoder.
+                            if !self.finishing && self.entries_taken < self.entries_started {
+                                return Ok(());
+                            }
+                            return Err(e);
+                        }
+                    }
+                    let curr_entry = self.curr_entry.as_ref().expect("entry data without entry");
+                    let remaining = curr_entry
+                        .header
+                        .compressed_size
+                        .saturating_sub(self.data_read);
+                    let len = remaining.min(self.buffer.remaining() as u64);
+                    self.take_data(len as usize)?;
+                    if len < remaining {
+                        return Ok(());
+                    }
+                    self.end_data()?;
+                }
+                State::DeflatedData => {
+                    self.create_decoder()?;
+                    let decoder = self
+                        .decoder
+                        .as_mut()
+                        .expect("deflated data without decoder");
+                    let events = &mut self.events;
+                    let emit = self.streaming && !self.skipping;
+                    let consumed = decoder.decode(&self.buffer, |data| {
+                        if emit {
+                            push_data(events, data)
+                        }
+                    })?;
+                    let finished = decoder.is_finished();
+                    self.take_compressed(consumed);
+                    if !finished {
+                        return Ok(());
+                    }
+                    self.end_data()?;
+                }
+                State::StoredData => {
+                    self.create_decoder()?;
+                    let curr_entry = self.curr_entry.as_ref().expect("entry data without entry");
+                    let decoder = self.decoder.as_ref().expect("stored data without decoder");
+                    let zip64 = curr_entry.header.zip64.is_some();
+                    let (len, found) = find_stored_data_end(&self.buffer, decoder, zip64);
+                    self.take_data(len)?;
+                    if !found {
+                        return Ok(());
+                    }
+                    self.end_data()?;
                 }
-                if let Header::LocalFile(local) = &header {
-                    let mut new_entry = ZipEntry::new(local.clone());
-                    let copy = if local.compressed_size as usize > self.buffer.remaining() {
-                        self.buffer.remaining()
-                    } else {
-                        local.compressed_size as usize
+                State::DataDescriptor => {
+                    let curr_entry = self.curr_entry.as_mut().expect("descriptor without entry");
+                    let zip64 = curr_entry.header.zip64.is_some();
+                    let descriptor = match decode_data_descriptor(&mut self.buffer, zip64) {
+                        Some(descriptor) => descriptor,
+                        None => return Ok(()),
                     };
-                    new_entry.bytes.extend(self.buffer.copy_to_bytes(copy));
-                    self.curr_entry = Some(new_entry);
-                    continue;
+                    curr_entry.header.crc32 = descriptor.crc32;
+                    curr_entry.header.compressed_size = descriptor.compressed_size;
+                    curr_entry.header.uncompressed_size = descriptor.uncompressed_size;
+                    self.end_entry()?;
                 }
-                if let Header::DataDescriptor(data) = &header {
-                    if let Some(curr_entry) = self.curr_entry.as_mut() {
-                        curr_entry.header.crc32 = data.crc32;
-                        curr_entry.header.compressed_size = data.compressed_size;
-                        curr_entry.header.uncompressed_size = data.uncompressed_size;
+                State::Trailing => {
+                    if !self.buffer.is_empty() && self.end_of_central_directory.is_some() {
+                        let offset = self.bytes_received - self.buffer.len() as u64;
+                        self.trailing_data.get_or_insert(offset);
                     }
+                    self.buffer.clear();
+                    return Ok(());
                 }
-                i = self.buffer.len() - self.buffer.remaining();
-                continue;
-            } else {
-                i += 1;
-                continue;
             }
         }
+    }
 
-        if let Some(curr_entry) = self.curr_entry.as_mut() {
-            let mut remaining = curr_entry.header.compressed_size as usize - curr_entry.bytes.len();
-            if remaining > self.buffer.remaining() {
-                remaining = self.buffer.remaining();
+    fn start_entry(&mut self, header: LocalFileHeader) -> Result<(), crate::Error> {
+        // With a data descriptor, the sizes in the local header may be left at zero,
+        // in which case the end of the data has to be found from the data itself.
+        let unknown_size = header.has_data_descriptor() && header.compressed_size == 0;
+        let method = header.compression_method();
+        self.state = match method {
+            CompressionMethod::Stored if unknown_size => State::StoredData,
+            _ if unknown_size => State::DeflatedData,
+            _ => State::Data,
+        };
+        self.skipping = self
+            .filter
+            .as_mut()
+            .is_some_and(|filter| !(filter.0)(&header));
+        // The decoder is only created once the data is read, so that entries that can
+        // not be decompressed can still be skipped.
+        self.decoder = None;
+        self.data_read = 0;
+        if self.streaming && !self.skipping {
+            self.entries_started += 1;
+            self.events.push_back(ZipEvent::EntryStart(header.clone()));
+        }
+        let mut entry = if self.streaming || self.skipping {
+            // The data is not collected, so there is no need to reserve room for it.
+            ZipEntry {
+                kind: header.kind(),
+                header,
+                bytes: BytesMut::new(),
+                offset: 0,
+                times: None,
             }
+        } else {
+            ZipEntry::new(header)
+        };
+        entry.offset = self.entry_offset;
+        self.curr_entry = Some(entry);
+        Ok(())
+    }
+
+    /// Creates the decoder of the current entry, unless it already has one.
+    ///
+    /// Fails when the compression method of the entry is not supported.
+    fn create_decoder(&mut self) -> Result<(), crate::Error> {
+        if self.decoder.is_none() {
+            let curr_entry = self.curr_entry.as_ref().expect("entry data without entry");
+            self.decoder = Some(Decoder::new(curr_entry.header.compression_method())?);
+        }
+        Ok(())
+    }
 
-            if remaining > 0 {
-                curr_entry.bytes.extend(&self.buffer.split_to(remaining));
+    /// Passes the next `len` bytes of entry data on to the decoder, if any.
+    ///
+    /// Skipped data of a known size does not need to be decoded at all.
+    fn take_data(&mut self, len: usize) -> Result<(), crate::Error> {
+        let decode = !self.skipping || !matches!(self.state, State::Data);
+        if let Some(decoder) = self.decoder.as_mut().filter(|_| decode) {
+            let events = &mut self.events;
+            let emit = self.streaming && !self.skipping;
+            decoder.decode(&self.buffer[..len], |data| {
+                if emit {
+                    push_data(events, data)
+                }
+            })?;
+        }
+        self.take_compressed(len);
+        Ok(())
+    }
+
+    /// Removes the next `len` bytes of entry data from the buffer, collecting them
+    /// into the current entry unless streaming or skipping.
+    fn take_compressed(&mut self, len: usize) {
+        let data = self.buffer.split_to(len);
+        self.data_read += len as u64;
+        if !self.streaming && !self.skipping {
+            if let Some(curr_entry) = self.curr_entry.as_mut() {
+                curr_entry.bytes.extend(&data);
             }
         }
     }
+
+    fn end_data(&mut self) -> Result<(), crate::Error> {
+        if self
+            .curr_entry
+            .as_ref()
+            .is_some_and(|entry| entry.header.has_data_descriptor())
+        {
+            self.state = State::DataDescriptor;
+            Ok(())
+        } else {
+            self.end_entry()
+        }
+    }
+
+    fn end_entry(&mut self) -> Result<(), crate::Error> {
+        self.state = State::Header;
+        let curr_entry = match self.curr_entry.take() {
+            Some(curr_entry) => curr_entry,
+            None => return Ok(()),
+        };
+        let decoder = self.decoder.take();
+        if let Some(entries) = self.validated_entries.as_mut() {
+            let header = &curr_entry.header;
+            entries.push(EntrySummary {
+                offset: self.entry_offset,
+                name: header.filename.clone(),
+                compression: header.compression,
+                crc32: header.crc32,
+                compressed_size: self.data_read,
+                uncompressed_size: header.uncompressed_size,
+            });
+        }
+        if std::mem::take(&mut self.skipping) {
+            return Ok(());
+        }
+        if self.streaming {
+            if let Some(decoder) = decoder {
+                decoder.verify(curr_entry.header.crc32, curr_entry.header.uncompressed_size)?;
+            }
+            let header = &curr_entry.header;
+            self.events.push_back(ZipEvent::EntryEnd(DataDescriptor {
+                crc32: header.crc32,
+                compressed_size: header.compressed_size,
+                uncompressed_size: header.uncompressed_size,
+            }));
+        } else {
+            self.entries.push_back(curr_entry);
+        }
+        Ok(())
+    }
+}
+
+/// Drops the data before the first local file header or end of central directory
+/// signature in `buffer`.
+///
+/// Returns false when there is none, keeping what could be the start of a signature
+/// split across updates.
+fn skip_to_signature(buffer: &mut BytesMut) -> bool {
+    match buffer
+        .windows(H_LOCAL_FILE.len())
+        .position(|window| window == H_LOCAL_FILE || window == H_EO_CENTRAL_DIRECTORY)
+    {
+        Some(pos) => {
+            buffer.advance(pos);
+            true
+        }
+        None => {
+            let partial = (H_LOCAL_FILE.len() - 1).min(buffer.len());
+            buffer.advance(buffer.len() - partial);
+            false
+        }
+    }
+}
+
+/// Queues a chunk of decompressed data as an event.
+fn push_data(events: &mut VecDeque<ZipEvent>, data: &[u8]) {
+    if !data.is_empty() {
+        events.push_back(ZipEvent::Data(Bytes::copy_from_slice(data)));
+    }
+}
+
+/// Looks for the data descriptor ending stored data of unknown size in `buffer`.
+///
+/// A descriptor only matches when its sizes and CRC-32 agree with the data before it,
+/// so that a signature appearing within the data is not mistaken for the end of it.
+/// Returns how many bytes of `buffer` are entry data, and whether the end was found.
+fn find_stored_data_end(buffer: &[u8], decoder: &Decoder, zip64: bool) -> (usize, bool) {
+    let descriptor_size = DataDescriptor::size(zip64) + H_DATA_DESCRIPTOR.len();
+    let mut searched = 0;
+    while let Some(pos) = buffer[searched..]
+        .windows(H_DATA_DESCRIPTOR.len())
+        .position(|window| window == H_DATA_DESCRIPTOR)
+    {
+        let pos = searched + pos;
+        if buffer.len() - pos < descriptor_size {
+            // Wait for the whole descriptor before looking any further.
+            return (pos, false);
+        }
+        let descriptor = decode_data_descriptor(
+            &mut BytesMut::from(&buffer[pos..pos + descriptor_size]),
+            zip64,
+        )
+        .expect("descriptor is complete");
+        let size = decoder.uncompressed_size() + pos as u64;
+        let mut hasher =
+            crc32fast::Hasher::new_with_initial_len(decoder.crc32(), decoder.uncompressed_size());
+        hasher.update(&buffer[..pos]);
+        if descriptor.compressed_size == size
+            && descriptor.uncompressed_size == size
+            && descriptor.crc32 == hasher.finalize()
+        {
+            return (pos, true);
+        }
+        searched = pos + 1;
+    }
+    // Keep what could be the start of a signature split across updates.
+    let partial = (H_DATA_DESCRIPTOR.len() - 1).min(buffer.len() - searched);
+    (buffer.len() - partial, false)
+}
+
+/// The times of an entry, once taken from its central directory record.
+#[derive(Debug, Clone, Copy)]
+struct EntryTimes {
+    modified: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    created: Option<SystemTime>,
+}
+
+impl EntryTimes {
+    /// Takes the times recorded in the extra fields of `record`, keeping the others
+    /// from `header`.
+    fn reconcile(
+        header: &LocalFileHeader,
+        times: Option<EntryTimes>,
+        record: &CentralDirectoryHeader,
+    ) -> Self {
+        let times = times.unwrap_or_else(|| EntryTimes {
+            modified: header.modified(),
+            accessed: header.accessed(),
+            created: header.created(),
+        });
+        let record = record.extra_times();
+        EntryTimes {
+            modified: record.modified.or(times.modified),
+            accessed: record.accessed.or(times.accessed),
+            created: record.created.or(times.created),
+        }
+    }
+}
+
+/// Whether `record` points to the entry at `offset` with the local header `header`.
+fn points_to(record: &CentralDirectoryHeader, header: &LocalFileHeader, offset: u64) -> bool {
+    record.relative_offset_of_local_header == offset && record.filename == header.filename
 }
 
 #[derive(Debug)]
 pub struct ZipEntry {
     header: LocalFileHeader,
     bytes: BytesMut,
-    name: String,
+    kind: EntryKind,
+    offset: u64,
+    /// The times from the central directory, once reconciled with it.
+    times: Option<EntryTimes>,
 }
 
 impl ZipEntry {
     pub fn new(header: LocalFileHeader) -> Self {
         Self {
-            bytes: BytesMut::with_capacity(header.compressed_size as usize),
+            bytes: BytesMut::with_capacity(header.compressed_size.min(MAX_PREALLOCATED) as usize),
+            kind: header.kind(),
             header,
-            name: String::new(),
+            offset: 0,
+            times: None,
         }
     }
+
+    /// Returns what the entry holds.
+    ///
+    /// This is taken from the local header until reconciled with the central
+    /// directory, see [reconcile_kind](Self::reconcile_kind).
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    /// Returns true when the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Directory
+    }
+
+    /// Returns true when the entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.kind == EntryKind::Symlink
+    }
+
+    /// Returns the offset of the local header of the entry in the archive.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Takes the kind of the entry from its central directory record, which also
+    /// tells symbolic links apart.
+    ///
+    /// Returns false, leaving the kind unchanged, when `record` does not point to
+    /// this entry by offset and name.
+    pub fn reconcile_kind(&mut self, record: &CentralDirectoryHeader) -> bool {
+        if !points_to(record, &self.header, self.offset) {
+            return false;
+        }
+        self.kind = record.kind();
+        true
+    }
+
+    /// Takes the times of the entry from the extra fields of its central directory
+    /// record, which some writers only fill there.
+    ///
+    /// Returns false, leaving the times unchanged, when `record` does not point to
+    /// this entry by offset and name.
+    pub fn reconcile_times(&mut self, record: &CentralDirectoryHeader) -> bool {
+        if !points_to(record, &self.header, self.offset) {
+            return false;
+        }
+        self.times = Some(EntryTimes::reconcile(&self.header, self.times, record));
+        true
+    }
+
+    /// Returns the filename of the zip entry.
     pub fn name(&self) -> &str {
-        &self.name
+        &self.header.filename
     }
 
     pub fn compressed_data(&self) -> &BytesMut {
@@ -341,12 +1649,89 @@ impl ZipEntry {
         &self.header
     }
 
+    /// Returns the compressed size of the data.
+    pub fn compressed_size(&self) -> u64 {
+        self.header.compressed_size
+    }
+
+    /// Returns the uncompressed size of the data.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.header.uncompressed_size
+    }
+
+    /// Returns the CRC-32 of the uncompressed data.
+    pub fn crc32(&self) -> u32 {
+        self.header.crc32
+    }
+
+    /// Returns the last modification time of the entry.
+    pub fn last_modified(&self) -> DosDateTime {
+        self.header.last_modified()
+    }
+
+    /// Returns the last modification time of the entry as a `SystemTime`. See
+    /// [LocalFileHeader::modified].
+    ///
+    /// Once reconciled with the central directory, see
+    /// [reconcile_times](Self::reconcile_times), the times recorded in its extra
+    /// fields take precedence.
+    pub fn modified(&self) -> Option<SystemTime> {
+        match self.times {
+            Some(times) => times.modified,
+            None => self.header.modified(),
+        }
+    }
+
+    /// Returns the last access time of the entry, if recorded in an extra field.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        match self.times {
+            Some(times) => times.accessed,
+            None => self.header.accessed(),
+        }
+    }
+
+    /// Returns the creation time of the entry, if recorded in an extra field.
+    pub fn created(&self) -> Option<SystemTime> {
+        match self.times {
+            Some(times) => times.created,
+            None => self.header.created(),
+        }
+    }
+
+    /// Returns the general purpose flags of the entry.
+    pub fn flags(&self) -> u16 {
+        self.header.flags
+    }
+
+    /// Returns the compression method of the entry.
+    pub fn compression_method(&self) -> CompressionMethod {
+        self.header.compression_method()
+    }
+
+    /// Decompresses the entry according to its compression method, and checks the
+    /// result against the size and CRC-32 recorded in the header (or the data
+    /// descriptor that followed it).
     pub fn inflate(self) -> Result<DeflatedEntry, crate::Error> {
-        let bytes = self.bytes;
+        let method = self.header.compression_method();
+        let mut decoder = Decoder::new(method)?;
+        let bytes = if method == CompressionMethod::Stored {
+            decoder.decode(&self.bytes, |_| {})?;
+            self.bytes.freeze()
+        } else {
+            let mut bytes = BytesMut::with_capacity(
+                self.header.uncompressed_size.min(MAX_PREALLOCATED) as usize,
+            );
+            decoder.decode(&self.bytes, |data| bytes.extend_from_slice(data))?;
+            bytes.freeze()
+        };
+        decoder.verify(self.header.crc32, self.header.uncompressed_size)?;
 
         Ok(DeflatedEntry {
-            bytes: inflate::inflate_bytes(&bytes).unwrap().into(),
+            bytes,
             header: self.header,
+            kind: self.kind,
+            offset: self.offset,
+            times: self.times,
         })
     }
 }
@@ -355,6 +1740,9 @@ impl ZipEntry {
 pub struct DeflatedEntry {
     header: LocalFileHeader,
     bytes: Bytes,
+    kind: EntryKind,
+    offset: u64,
+    times: Option<EntryTimes>,
 }
 
 impl DeflatedEntry {
@@ -374,14 +1762,104 @@ impl DeflatedEntry {
     }
 
     /// Returns the compressed size of the data.
-    pub fn compressed_size(&self) -> u32 {
+    pub fn compressed_size(&self) -> u64 {
         self.header.compressed_size
     }
 
     /// Returns the uncompressed size of the data.
-    pub fn uncompressed_size(&self) -> u32 {
+    pub fn uncompressed_size(&self) -> u64 {
         self.header.uncompressed_size
     }
+
+    /// Returns the CRC-32 of the data.
+    pub fn crc32(&self) -> u32 {
+        self.header.crc32
+    }
+
+    /// Returns the last modification time of the entry.
+    pub fn last_modified(&self) -> DosDateTime {
+        self.header.last_modified()
+    }
+
+    /// Returns the last modification time of the entry as a `SystemTime`. See
+    /// [ZipEntry::modified].
+    pub fn modified(&self) -> Option<SystemTime> {
+        match self.times {
+            Some(times) => times.modified,
+            None => self.header.modified(),
+        }
+    }
+
+    /// Returns the last access time of the entry, if recorded in an extra field.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        match self.times {
+            Some(times) => times.accessed,
+            None => self.header.accessed(),
+        }
+    }
+
+    /// Returns the creation time of the entry, if recorded in an extra field.
+    pub fn created(&self) -> Option<SystemTime> {
+        match self.times {
+            Some(times) => times.created,
+            None => self.header.created(),
+        }
+    }
+
+    /// Returns the general purpose flags of the entry.
+    pub fn flags(&self) -> u16 {
+        self.header.flags
+    }
+
+    /// Returns the compression method the data was stored with.
+    pub fn compression_method(&self) -> CompressionMethod {
+        self.header.compression_method()
+    }
+
+    /// Returns the local header of the entry.
+    pub fn header(&self) -> &LocalFileHeader {
+        &self.header
+    }
+
+    /// Returns what the entry holds. See [ZipEntry::kind].
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    /// Returns true when the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Directory
+    }
+
+    /// Returns true when the entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.kind == EntryKind::Symlink
+    }
+
+    /// Returns the offset of the local header of the entry in the archive.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Takes the kind of the entry from its central directory record. See
+    /// [ZipEntry::reconcile_kind].
+    pub fn reconcile_kind(&mut self, record: &CentralDirectoryHeader) -> bool {
+        if !points_to(record, &self.header, self.offset) {
+            return false;
+        }
+        self.kind = record.kind();
+        true
+    }
+
+    /// Takes the times of the entry from its central directory record. See
+    /// [ZipEntry::reconcile_times].
+    pub fn reconcile_times(&mut self, record: &CentralDirectoryHeader) -> bool {
+        if !points_to(record, &self.header, self.offset) {
+            return false;
+        }
+        self.times = Some(EntryTimes::reconcile(&self.header, self.times, record));
+        true
+    }
 }
 
 impl From<DeflatedEntry> for Bytes {
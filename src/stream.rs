@@ -0,0 +1,314 @@
+use std::{
+    future::poll_fn,
+    io,
+    path::Path,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{
+    extract, EntryKind, Error, ExtractOutcome, ExtractedEntry, LocalFileHeader, ZipEntry, ZipEvent,
+    ZipReader,
+};
+
+/// Size of the chunks read from the underlying reader by default.
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Reads the next chunk of `reader` into `zip_reader`, finishing it at the end of the input.
+///
+/// Returns `true` once the input has ended.
+fn poll_feed<R>(
+    reader: &mut R,
+    buf: &mut [u8],
+    zip_reader: &mut ZipReader,
+    cx: &mut Context<'_>,
+) -> Poll<Result<bool, Error>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = ReadBuf::new(buf);
+    ready!(Pin::new(reader).poll_read(cx, &mut buf))?;
+
+    if buf.filled().is_empty() {
+        return Poll::Ready(zip_reader.finish().map(|_| true));
+    }
+
+    Poll::Ready(
+        zip_reader
+            .update(Bytes::copy_from_slice(buf.filled()))
+            .map(|_| false),
+    )
+}
+
+/// Reads the given `AsyncRead` in chunks and yields one `ZipEntry` at a time.
+///
+/// This is the async counterpart of [ZipIterator](crate::ZipIterator). The stream
+/// ends after the first error.
+pub struct ZipStream<R> {
+    reader: R,
+    zip_reader: ZipReader,
+    buf: Box<[u8]>,
+    done: bool,
+}
+
+impl<R> ZipStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Creates a stream that reads chunks of up to `capacity` bytes at a time.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            zip_reader: ZipReader::default(),
+            buf: vec![0; capacity].into_boxed_slice(),
+            done: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> From<R> for ZipStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn from(value: R) -> Self {
+        ZipStream::new(value)
+    }
+}
+
+impl<R> Stream for ZipStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    type Item = Result<ZipEntry, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.zip_reader.take_entry() {
+                None if this.done => return Poll::Ready(this.zip_reader.take_error().map(Err)),
+                None => {
+                    match ready!(poll_feed(
+                        &mut this.reader,
+                        &mut this.buf,
+                        &mut this.zip_reader,
+                        cx
+                    )) {
+                        Ok(done) => this.done = done,
+                        Err(e) => {
+                            this.done = true;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    }
+                }
+                entry => return Poll::Ready(entry.map(Ok)),
+            }
+        }
+    }
+}
+
+/// Reads a zip archive from an `AsyncRead`, giving out one entry at a time.
+///
+/// The contents of each entry are decompressed as they are read from the
+/// [AsyncZipStreamEntry], so memory use does not depend on the size of the entries.
+/// The reader ends after the first error.
+pub struct AsyncZipStreamReader<R> {
+    reader: R,
+    zip_reader: ZipReader,
+    buf: Box<[u8]>,
+    done: bool,
+    /// Decompressed data of the current entry that has not been read yet.
+    chunk: Bytes,
+    /// Whether the data of the current entry has not been read entirely.
+    in_entry: bool,
+}
+
+impl<R> AsyncZipStreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Creates a reader that reads chunks of up to `capacity` bytes at a time.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            zip_reader: ZipReader::streaming(),
+            buf: vec![0; capacity].into_boxed_slice(),
+            done: false,
+            chunk: Bytes::new(),
+            in_entry: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn skip_entry(&mut self) {
+        self.chunk.clear();
+        if self.in_entry {
+            self.zip_reader.skip_entry();
+            self.in_entry = false;
+        }
+    }
+}
+
+impl<R> From<R> for AsyncZipStreamReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn from(value: R) -> Self {
+        AsyncZipStreamReader::new(value)
+    }
+}
+
+impl<R> AsyncZipStreamReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Returns the next entry of the archive, or `None` once the archive has been read.
+    ///
+    /// Whatever was not read of the previous entry is skipped without being decompressed.
+    pub async fn next_entry(&mut self) -> Result<Option<AsyncZipStreamEntry<'_, R>>, Error> {
+        self.skip_entry();
+        loop {
+            match poll_fn(|cx| self.poll_event(cx)).await? {
+                Some(ZipEvent::EntryStart(header)) => {
+                    self.in_entry = true;
+                    return Ok(Some(AsyncZipStreamEntry {
+                        archive: self,
+                        header,
+                    }));
+                }
+                Some(ZipEvent::EntryEnd(_)) => self.in_entry = false,
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Extracts the rest of the archive below `dir`, which is created if needed.
+    ///
+    /// See [ZipStreamReader::extract_to](crate::ZipStreamReader::extract_to).
+    pub async fn extract_to(
+        &mut self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<ExtractedEntry>, Error> {
+        let target = extract::prepare_target_async(dir.as_ref()).await?;
+        let mut report = Vec::new();
+        while let Some(mut entry) = self.next_entry().await? {
+            let (name, kind) = (entry.name().to_owned(), entry.kind());
+            let extracted = extract::extract_entry_async(&target, name, kind, &mut entry).await;
+            let read_failed = matches!(extracted.outcome, ExtractOutcome::ReadFailed(_));
+            report.push(extracted);
+            if read_failed {
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<ZipEvent>, Error>> {
+        loop {
+            match self.zip_reader.take_event() {
+                None if self.done => {
+                    return Poll::Ready(self.zip_reader.take_error().map_or(Ok(None), Err))
+                }
+                None => {
+                    match ready!(poll_feed(
+                        &mut self.reader,
+                        &mut self.buf,
+                        &mut self.zip_reader,
+                        cx
+                    )) {
+                        Ok(done) => self.done = done,
+                        Err(e) => {
+                            self.done = true;
+                            self.in_entry = false;
+                            return Poll::Ready(Err(e));
+                        }
+                    }
+                }
+                event => return Poll::Ready(Ok(event)),
+            }
+        }
+    }
+
+    fn poll_read_entry(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        while self.chunk.is_empty() && self.in_entry {
+            match ready!(self.poll_event(cx))? {
+                Some(ZipEvent::Data(data)) => self.chunk = data,
+                Some(ZipEvent::EntryEnd(_)) => self.in_entry = false,
+                Some(_) | None => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "archive ended within an entry",
+                    )))
+                }
+            }
+        }
+        let len = self.chunk.len().min(buf.remaining());
+        buf.put_slice(&self.chunk[..len]);
+        self.chunk.advance(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// An entry of an [AsyncZipStreamReader], whose decompressed contents are read
+/// through `AsyncRead`.
+///
+/// The contents are checked against the size and CRC-32 of the entry as they are read,
+/// and a mismatch is reported as an error once the end of the entry is reached.
+pub struct AsyncZipStreamEntry<'a, R> {
+    archive: &'a mut AsyncZipStreamReader<R>,
+    header: LocalFileHeader,
+}
+
+impl<R> AsyncZipStreamEntry<'_, R> {
+    /// Returns the local header of the entry.
+    pub fn header(&self) -> &LocalFileHeader {
+        &self.header
+    }
+
+    /// Returns the filename of the entry.
+    pub fn name(&self) -> &str {
+        &self.header.filename
+    }
+
+    /// Returns what the entry holds, as far as the local header tells.
+    pub fn kind(&self) -> EntryKind {
+        self.header.kind()
+    }
+
+    /// Skips the rest of the entry without decompressing it.
+    pub fn skip(self) {
+        self.archive.skip_entry();
+    }
+}
+
+impl<R> AsyncRead for AsyncZipStreamEntry<'_, R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.get_mut().archive.poll_read_entry(cx, buf)
+    }
+}
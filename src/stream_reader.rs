@@ -0,0 +1,210 @@
+use std::{
+    io::{self, Read},
+    path::Path,
+};
+
+use bytes::{Buf, Bytes};
+
+use crate::{
+    extract, EntryKind, Error, ExtractOutcome, ExtractedEntry, LocalFileHeader, ZipEvent, ZipReader,
+};
+
+/// Size of the chunks read from the underlying reader by default.
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Reads a zip archive from a `Read`, giving out one entry at a time.
+///
+/// Unlike [ZipIterator](crate::ZipIterator), the contents of each entry are
+/// decompressed as they are read from the [ZipStreamEntry], so memory use does not
+/// depend on the size of the entries. The reader ends after the first error.
+pub struct ZipStreamReader<R> {
+    reader: R,
+    zip_reader: ZipReader,
+    buf: Box<[u8]>,
+    done: bool,
+    /// Decompressed data of the current entry that has not been read yet.
+    chunk: Bytes,
+    /// Whether the data of the current entry has not been read entirely.
+    in_entry: bool,
+}
+
+impl<R> ZipStreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Creates a reader that reads chunks of up to `capacity` bytes at a time.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            zip_reader: ZipReader::streaming(),
+            buf: vec![0; capacity].into_boxed_slice(),
+            done: false,
+            chunk: Bytes::new(),
+            in_entry: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn skip_entry(&mut self) {
+        self.chunk.clear();
+        if self.in_entry {
+            self.zip_reader.skip_entry();
+            self.in_entry = false;
+        }
+    }
+}
+
+impl<R> From<R> for ZipStreamReader<R>
+where
+    R: Read,
+{
+    fn from(value: R) -> Self {
+        ZipStreamReader::new(value)
+    }
+}
+
+impl<R> ZipStreamReader<R>
+where
+    R: Read,
+{
+    /// Returns the next entry of the archive, or `None` once the archive has been read.
+    ///
+    /// Whatever was not read of the previous entry is skipped without being decompressed.
+    pub fn next_entry(&mut self) -> Result<Option<ZipStreamEntry<'_, R>>, Error> {
+        self.skip_entry();
+        loop {
+            match self.next_event()? {
+                Some(ZipEvent::EntryStart(header)) => {
+                    self.in_entry = true;
+                    return Ok(Some(ZipStreamEntry {
+                        archive: self,
+                        header,
+                    }));
+                }
+                Some(ZipEvent::EntryEnd(_)) => self.in_entry = false,
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Extracts the rest of the archive below `dir`, which is created if needed.
+    ///
+    /// Entries whose name is not a safe relative path, or whose path goes through an
+    /// existing symbolic link out of `dir`, are left out and reported as such. So are
+    /// the entries that can not be written. An entry that can not be read is removed
+    /// and reported as [ExtractOutcome::ReadFailed], and ends the extraction, while
+    /// errors reading the archive between entries are returned.
+    /// Symbolic links are only known from the central directory, so they are written
+    /// as files holding the path they point to.
+    pub fn extract_to(&mut self, dir: impl AsRef<Path>) -> Result<Vec<ExtractedEntry>, Error> {
+        let target = extract::prepare_target(dir.as_ref())?;
+        let mut report = Vec::new();
+        while let Some(mut entry) = self.next_entry()? {
+            let (name, kind) = (entry.name().to_owned(), entry.kind());
+            let extracted = extract::extract_entry(&target, name, kind, &mut entry);
+            let read_failed = matches!(extracted.outcome, ExtractOutcome::ReadFailed(_));
+            report.push(extracted);
+            if read_failed {
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    fn next_event(&mut self) -> Result<Option<ZipEvent>, Error> {
+        loop {
+            match self.zip_reader.take_event() {
+                None if self.done => return self.zip_reader.take_error().map_or(Ok(None), Err),
+                None => {
+                    if let Err(e) = self.read_chunk() {
+                        self.done = true;
+                        self.in_entry = false;
+                        return Err(e);
+                    }
+                }
+                event => return Ok(event),
+            }
+        }
+    }
+
+    fn read_chunk(&mut self) -> Result<(), Error> {
+        let num = loop {
+            match self.reader.read(&mut self.buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+
+        if num == 0 {
+            self.done = true;
+            return self.zip_reader.finish();
+        }
+
+        self.zip_reader
+            .update(Bytes::copy_from_slice(&self.buf[..num]))
+    }
+
+    fn read_entry(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() && self.in_entry {
+            match self.next_event()? {
+                Some(ZipEvent::Data(data)) => self.chunk = data,
+                Some(ZipEvent::EntryEnd(_)) => self.in_entry = false,
+                Some(_) | None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "archive ended within an entry",
+                    ))
+                }
+            }
+        }
+        let len = self.chunk.len().min(buf.len());
+        self.chunk.copy_to_slice(&mut buf[..len]);
+        Ok(len)
+    }
+}
+
+/// An entry of a [ZipStreamReader], whose decompressed contents are read through `Read`.
+///
+/// The contents are checked against the size and CRC-32 of the entry as they are read,
+/// and a mismatch is reported as an error once the end of the entry is reached.
+pub struct ZipStreamEntry<'a, R> {
+    archive: &'a mut ZipStreamReader<R>,
+    header: LocalFileHeader,
+}
+
+impl<R> ZipStreamEntry<'_, R> {
+    /// Returns the local header of the entry.
+    pub fn header(&self) -> &LocalFileHeader {
+        &self.header
+    }
+
+    /// Returns the filename of the entry.
+    pub fn name(&self) -> &str {
+        &self.header.filename
+    }
+
+    /// Returns what the entry holds, as far as the local header tells.
+    pub fn kind(&self) -> EntryKind {
+        self.header.kind()
+    }
+
+    /// Skips the rest of the entry without decompressing it.
+    pub fn skip(self) {
+        self.archive.skip_entry();
+    }
+}
+
+impl<R> Read for ZipStreamEntry<'_, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.archive.read_entry(buf)
+    }
+}